@@ -0,0 +1,98 @@
+//! Computer-controlled snakes. A bot looks at the board each tick and, like
+//! a player pressing a key, asks for a turn; the simulation takes it through
+//! the same input queue as anyone else's.
+
+use crate::pathfind::Grid;
+use crate::sim::Simulation;
+use crate::snake::Direction;
+
+const DIRECTIONS: [Direction; 4] = [Direction::Up, Direction::Down, Direction::Left, Direction::Right];
+
+/// How hard a computer player tries.
+#[derive(Copy, Clone, PartialEq)]
+pub enum Difficulty {
+    Greedy,  // Heads straight for the nearest food, dodging only what is right in front
+    Careful, // Takes the shortest way round to the food
+    Planner, // Only goes for food when there is room to get out again
+}
+
+pub const ALL_DIFFICULTIES: [Difficulty; 3] = [Difficulty::Greedy, Difficulty::Careful, Difficulty::Planner];
+
+impl Difficulty {
+    pub fn get_name(&self) -> &str {
+        match self {
+            Difficulty::Greedy => "GREEDY",
+            Difficulty::Careful => "CAREFUL",
+            Difficulty::Planner => "PLANNER",
+        }
+    }
+}
+
+/// The turn `player`'s bot wants to make this tick, if any. Bots wait
+/// until their last turn has been made before asking for another.
+pub fn choose_direction(sim: &Simulation, player: usize, difficulty: Difficulty) -> Option<Direction> {
+    if !sim.is_alive(player) || sim.queued_turns(player) > 0 {
+        return None;
+    }
+    let snake = &sim.snakes()[player];
+    let head = snake.head_position();
+    let heading = snake.head_direction();
+
+    // Only the greedy bot forgets that tails move on
+    let grid = Grid::from_sim(sim, difficulty != Difficulty::Greedy);
+    let foods: Vec<(i32, i32)> = sim.foods().iter().map(|food| food.position()).collect();
+
+    // Moves that don't crash straight away, going on ahead first
+    let mut moves: Vec<Direction> = DIRECTIONS
+        .into_iter()
+        .filter(|&direction| direction != heading.opposite())
+        .filter(|&direction| {
+            let (x, y) = grid.neighbour(head, direction);
+            grid.is_open(x, y, 1)
+        })
+        .collect();
+    moves.sort_by_key(|&direction| direction != heading);
+
+    let choice = match difficulty {
+        Difficulty::Greedy => moves.into_iter().min_by_key(|&direction| {
+            let next = grid.neighbour(head, direction);
+            foods.iter().map(|&food| grid.distance(next, food)).min().unwrap_or(0)
+        }),
+        Difficulty::Careful => grid.first_step(head, |cell| foods.contains(&cell)).or(moves.first().copied()),
+        Difficulty::Planner => plan(sim, player, &grid, &foods, moves),
+    };
+    choice.filter(|&direction| direction != heading)
+}
+
+// Goes for the nearest food only if the cell the path starts on leaves at
+// least the snake's length of room; otherwise heads for the most room.
+// Cells another snake's head or an enemy could also move into are a last
+// resort.
+fn plan(sim: &Simulation, player: usize, grid: &Grid, foods: &[(i32, i32)], moves: Vec<Direction>) -> Option<Direction> {
+    let head = sim.snakes()[player].head_position();
+    let room_needed = sim.snakes()[player].len() + 1;
+
+    let others = (0..sim.snakes().len())
+        .filter(|&other| other != player && sim.is_alive(other))
+        .map(|other| sim.snakes()[other].head_position());
+    let threats: Vec<(i32, i32)> = others.chain(sim.enemies().iter().map(|enemy| enemy.position())).collect();
+    let contested = |direction: Direction| {
+        let next = grid.neighbour(head, direction);
+        threats.iter().any(|&threat| DIRECTIONS.iter().any(|&step| grid.neighbour(threat, step) == next))
+    };
+    let room = |direction: Direction| {
+        let (x, y) = grid.neighbour(head, direction);
+        grid.flood_fill((x, y), 1)
+    };
+
+    if let Some(direction) = grid.first_step(head, |cell| foods.contains(&cell))
+        && moves.contains(&direction)
+        && !contested(direction)
+        && room(direction) >= room_needed
+    {
+        return Some(direction);
+    }
+
+    // Keeps the first of equally good moves, so ahead when it can
+    moves.into_iter().rev().max_by_key(|&direction| (!contested(direction), room(direction)))
+}
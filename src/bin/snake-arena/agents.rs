@@ -0,0 +1,65 @@
+//! Agents built into the arena besides the game's own bots. Add your own
+//! controllers to `register` to have them play.
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use snake::{Direction, Observation, Registry, SnakeController};
+
+const DIRECTIONS: [Direction; 4] = [Direction::Up, Direction::Down, Direction::Left, Direction::Right];
+
+/// The game's bots plus every agent below.
+pub fn register() -> Registry {
+    let mut registry = Registry::default();
+    registry.register("STRAIGHT", || Box::new(Straight));
+    registry.register("RANDOM", || Box::new(Random { rng: None }));
+    registry
+}
+
+// Whether the cell one step `direction` from the head is clear of walls,
+// corpses, enemies and every body, tails included
+fn is_safe(observation: &Observation, direction: Direction) -> bool {
+    let (x, y) = observation.head();
+    let (dx, dy) = direction.offset();
+    let (x, y) = if observation.wraps() {
+        snake::snake::wrap_position(x + dx, y + dy, observation.width(), observation.height())
+    } else {
+        (x + dx, y + dy)
+    };
+    !observation.is_wall(x, y)
+        && !observation.obstacles().contains(&(x, y))
+        && !observation.enemies().iter().any(|enemy| enemy.check_collision(x, y))
+        && !observation.body().any(|cell| cell == (x, y))
+        && !observation.others().any(|(_, other)| other.contains(x, y))
+}
+
+/// Never turns: a floor every other agent should beat.
+struct Straight;
+
+impl SnakeController for Straight {
+    fn choose(&mut self, observation: &Observation) -> Direction {
+        observation.heading()
+    }
+}
+
+/// Takes a random move that doesn't crash straight away. Seeded from the
+/// match, so the same seed plays out the same.
+struct Random {
+    rng: Option<StdRng>,
+}
+
+impl SnakeController for Random {
+    fn choose(&mut self, observation: &Observation) -> Direction {
+        let seed = observation.sim().seed() ^ observation.player() as u64;
+        let rng = self.rng.get_or_insert_with(|| StdRng::seed_from_u64(seed));
+        let heading = observation.heading();
+        let moves: Vec<Direction> = DIRECTIONS
+            .into_iter()
+            .filter(|&direction| direction != heading.opposite() && is_safe(observation, direction))
+            .collect();
+        if moves.is_empty() {
+            return heading;
+        }
+        moves[rng.random_range(0..moves.len())]
+    }
+}
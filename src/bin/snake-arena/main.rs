@@ -0,0 +1,405 @@
+//! Headless tournaments between snake agents. Plays every pairing of the
+//! chosen agents on every seed, game mode and board size as fast as the
+//! simulation runs, then prints a report per agent and writes one CSV row
+//! per snake per match.
+//!
+//! ```text
+//! snake-arena --agents PLANNER,CAREFUL --seeds 1-50 --modes MEDIUM,HARD --sizes 30x30,60x40
+//! ```
+
+mod agents;
+
+use std::fmt::Write as _;
+use std::fs;
+use std::ops::RangeInclusive;
+use std::process;
+use std::time::Instant;
+
+use snake::controller::{next_turn, Registry, SnakeController};
+use snake::level::{clamp_board_size, Level, DEFAULT_BOARD_SIZE};
+use snake::mode::{CorpseRule, GameMode, PlayerMode, SurvivorRule};
+use snake::sim::{DeathCause, MatchConfig, MatchResult, Simulation, TickInputs, ALL_DEATH_CAUSES, TICK_RATE};
+
+const USAGE: &str = "\
+Usage: snake-arena [options]
+
+  --agents <A,B,...>   Agents to play, by name (default: all of them)
+  --players <mode>     solo, versus or coop (default: versus, solo for one agent)
+  --seeds <list>       Seeds and seed ranges, e.g. 1-100 or 3,7,10-20 (default: 1-10)
+  --modes <list>       Game modes, e.g. MEDIUM,HARD (default: MEDIUM)
+  --sizes <list>       Board sizes, e.g. 30x30,60x40 (default: the game's)
+  --max-seconds <n>    Game time after which a match is stopped (default: 300)
+  --wrap               Play with wrap-around walls
+  --csv <path>         Where to write per-match results (default: arena.csv)
+  --list               List the agents and exit";
+
+// Matches still going after this much game time are stopped; versus ones
+// then go to the higher score
+const DEFAULT_MAX_SECONDS: u64 = 300;
+const DEFAULT_CSV: &str = "arena.csv";
+
+struct Options {
+    agents: Vec<String>,
+    player_mode: PlayerMode,
+    seeds: Vec<RangeInclusive<u64>>, // Played lazily, so long ranges cost no memory
+    modes: Vec<GameMode>,
+    sizes: Vec<(i32, i32)>,
+    max_ticks: u64,
+    wrap_walls: bool,
+    csv: String,
+}
+
+/// How one snake did in a versus match.
+#[derive(Clone, Copy, PartialEq)]
+enum Outcome {
+    Win,
+    Loss,
+    Draw,
+}
+
+impl Outcome {
+    fn get_name(&self) -> &str {
+        match self {
+            Outcome::Win => "WIN",
+            Outcome::Loss => "LOSS",
+            Outcome::Draw => "DRAW",
+        }
+    }
+}
+
+/// One snake's part in a match.
+struct Entry {
+    agent: String,
+    score: i32,
+    length: usize,
+    death: Option<DeathCause>, // None if it was still alive at the end
+    outcome: Option<Outcome>, // Versus only
+}
+
+struct MatchRecord {
+    config: MatchConfig,
+    ticks: u64,
+    timed_out: bool, // Stopped at the time cap rather than played out
+    entries: Vec<Entry>,
+}
+
+// Totals for one agent across the tournament
+#[derive(Default)]
+struct Totals {
+    snakes: usize,
+    wins: usize,
+    losses: usize,
+    draws: usize,
+    length: usize,
+    score: i64,
+    deaths: [usize; ALL_DEATH_CAUSES.len()],
+    survived: usize,
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}\n\n{}", message, USAGE);
+    process::exit(1);
+}
+
+// The value after `name` on the command line, e.g. `--seeds 1-10`
+fn arg_value(args: &[String], name: &str) -> Option<String> {
+    args.iter().position(|arg| arg == name).map(|index| match args.get(index + 1) {
+        Some(value) => value.clone(),
+        None => fail(&format!("{} needs a value", name)),
+    })
+}
+
+// Comma-separated seeds, each either a number or an inclusive range `a-b`
+fn parse_seeds(value: &str) -> Result<Vec<RangeInclusive<u64>>, String> {
+    let number = |text: &str| text.trim().parse::<u64>().map_err(|_| format!("Bad seed {}", text));
+    let mut seeds = Vec::new();
+    for part in value.split(',') {
+        match part.split_once('-') {
+            Some((first, last)) => {
+                let (first, last) = (number(first)?, number(last)?);
+                if first > last {
+                    return Err(format!("Seed range {} runs backwards", part));
+                }
+                seeds.push(first..=last);
+            }
+            None => {
+                let seed = number(part)?;
+                seeds.push(seed..=seed);
+            }
+        }
+    }
+    Ok(seeds)
+}
+
+fn parse_sizes(value: &str) -> Option<Vec<(i32, i32)>> {
+    value
+        .split(',')
+        .map(|size| {
+            let (width, height) = size.trim().to_lowercase().split_once('x').map(|(w, h)| (w.to_string(), h.to_string()))?;
+            Some(clamp_board_size(width.parse().ok()?, height.parse().ok()?))
+        })
+        .collect()
+}
+
+fn parse_options(args: &[String], registry: &Registry) -> Options {
+    let agents: Vec<String> = match arg_value(args, "--agents") {
+        Some(value) => value.split(',').map(|name| name.trim().to_uppercase()).collect(),
+        None => registry.names().map(|name| name.to_string()).collect(),
+    };
+    if let Some(unknown) = agents.iter().find(|name| registry.create(name).is_none()) {
+        fail(&format!("No agent called {}", unknown));
+    }
+    if let Some(repeated) = agents.iter().enumerate().find_map(|(index, name)| agents[..index].contains(name).then_some(name)) {
+        fail(&format!("Agent {} is listed twice", repeated));
+    }
+
+    let player_mode = match arg_value(args, "--players").as_deref() {
+        Some("solo") => PlayerMode::Solo,
+        Some("versus") => PlayerMode::Versus,
+        Some("coop") => PlayerMode::CoOp,
+        Some(other) => fail(&format!("Unknown player mode {}", other)),
+        None if agents.len() == 1 => PlayerMode::Solo,
+        None => PlayerMode::Versus,
+    };
+
+    let seeds = match arg_value(args, "--seeds") {
+        Some(value) => parse_seeds(&value).unwrap_or_else(|err| fail(&err)),
+        None => vec![1..=10],
+    };
+
+    let modes = match arg_value(args, "--modes") {
+        Some(value) => value
+            .split(',')
+            .map(|name| GameMode::from_name(&name.trim().to_uppercase()).unwrap_or_else(|| fail(&format!("Unknown game mode {}", name))))
+            .collect(),
+        None => vec![GameMode::Medium],
+    };
+
+    let sizes = match arg_value(args, "--sizes") {
+        Some(value) => parse_sizes(&value).unwrap_or_else(|| fail(&format!("Bad board sizes {}", value))),
+        None => vec![(DEFAULT_BOARD_SIZE, DEFAULT_BOARD_SIZE)],
+    };
+
+    let max_ticks = match arg_value(args, "--max-seconds") {
+        Some(value) => value
+            .parse::<u64>()
+            .ok()
+            .and_then(|seconds| seconds.checked_mul(TICK_RATE as u64))
+            .unwrap_or_else(|| fail(&format!("Bad time cap {}", value))),
+        None => DEFAULT_MAX_SECONDS * TICK_RATE as u64,
+    };
+
+    Options {
+        agents,
+        player_mode,
+        seeds,
+        modes,
+        sizes,
+        max_ticks,
+        wrap_walls: args.iter().any(|arg| arg == "--wrap"),
+        csv: arg_value(args, "--csv").unwrap_or(DEFAULT_CSV.to_string()),
+    }
+}
+
+// Who plays whom: each agent alone in solo, otherwise every pair both ways
+// round so neither gets the better starting spot. A lone agent plays itself.
+fn pairings(agents: &[String], player_mode: PlayerMode) -> Vec<Vec<String>> {
+    if player_mode.player_count() == 1 {
+        return agents.iter().map(|agent| vec![agent.clone()]).collect();
+    }
+    if agents.len() == 1 {
+        return vec![vec![agents[0].clone(), agents[0].clone()]];
+    }
+    let mut pairings = Vec::new();
+    for first in agents {
+        for second in agents.iter().filter(|&second| second != first) {
+            pairings.push(vec![first.clone(), second.clone()]);
+        }
+    }
+    pairings
+}
+
+// The versus outcome for each player: the simulation's result, or the
+// scores if the match was stopped at the time cap
+fn outcomes(sim: &Simulation) -> Vec<Option<Outcome>> {
+    let result = sim.result().or_else(|| sim.score_result());
+    (0..sim.snakes().len())
+        .map(|player| {
+            result.map(|result| match result {
+                MatchResult::Winner(winner) if winner == player => Outcome::Win,
+                MatchResult::Winner(_) => Outcome::Loss,
+                MatchResult::Draw => Outcome::Draw,
+            })
+        })
+        .collect()
+}
+
+fn play(config: MatchConfig, agents: &[String], registry: &Registry, max_ticks: u64) -> MatchRecord {
+    let mut sim = Simulation::new(config, Level::open(config.width, config.height));
+    let mut controllers: Vec<Box<dyn SnakeController>> =
+        agents.iter().map(|name| registry.create(name).expect("agents are checked up front")).collect();
+
+    while !sim.is_game_over() && sim.tick() < max_ticks {
+        let mut inputs = TickInputs::default();
+        for (player, controller) in controllers.iter_mut().enumerate() {
+            inputs.directions[player] = next_turn(controller.as_mut(), &sim, player);
+        }
+        sim.step(&inputs);
+    }
+
+    let entries = agents
+        .iter()
+        .zip(outcomes(&sim))
+        .enumerate()
+        .map(|(player, (agent, outcome))| Entry {
+            agent: agent.clone(),
+            score: sim.scores()[player],
+            length: sim.snakes()[player].len(),
+            death: sim.death_cause(player),
+            outcome,
+        })
+        .collect();
+    MatchRecord {
+        config: *sim.config(),
+        ticks: sim.tick(),
+        timed_out: !sim.is_game_over(),
+        entries,
+    }
+}
+
+fn to_csv(records: &[MatchRecord]) -> String {
+    let mut csv = String::from("match,mode,players,width,height,seed,ticks,timed_out,player,agent,score,length,death,outcome\n");
+    for (index, record) in records.iter().enumerate() {
+        let config = &record.config;
+        for (player, entry) in record.entries.iter().enumerate() {
+            let _ = writeln!(
+                csv,
+                "{},{},{},{},{},{},{},{},{},{},{},{},{},{}",
+                index + 1,
+                config.game_mode.get_name(),
+                config.player_mode.get_name(),
+                config.width,
+                config.height,
+                config.seed,
+                record.ticks,
+                record.timed_out,
+                player + 1,
+                entry.agent,
+                entry.score,
+                entry.length,
+                entry.death.as_ref().map_or("ALIVE", |cause| cause.get_name()),
+                entry.outcome.as_ref().map_or("", |outcome| outcome.get_name()),
+            );
+        }
+    }
+    csv
+}
+
+fn print_report(agents: &[String], records: &[MatchRecord]) {
+    let mut totals: Vec<Totals> = agents.iter().map(|_| Totals::default()).collect();
+    for entry in records.iter().flat_map(|record| &record.entries) {
+        let totals = &mut totals[agents.iter().position(|agent| *agent == entry.agent).unwrap()];
+        totals.snakes += 1;
+        totals.length += entry.length;
+        totals.score += entry.score as i64;
+        match entry.outcome {
+            Some(Outcome::Win) => totals.wins += 1,
+            Some(Outcome::Loss) => totals.losses += 1,
+            Some(Outcome::Draw) => totals.draws += 1,
+            None => {}
+        }
+        match entry.death {
+            Some(cause) => totals.deaths[ALL_DEATH_CAUSES.iter().position(|&c| c == cause).unwrap()] += 1,
+            None => totals.survived += 1,
+        }
+    }
+
+    let name_width = agents.iter().map(|agent| agent.len()).max().unwrap_or(0).max("AGENT".len());
+    println!(
+        "{:<name_width$}  {:>6}  {:>5}  {:>6}  {:>5}  {:>8}  {:>10}  {:>9}",
+        "AGENT", "SNAKES", "WINS", "LOSSES", "DRAWS", "WIN RATE", "AVG LENGTH", "AVG SCORE"
+    );
+    for (agent, totals) in agents.iter().zip(&totals) {
+        let decided = totals.wins + totals.losses + totals.draws;
+        let win_rate = if decided > 0 { format!("{:.1}%", 100.0 * totals.wins as f64 / decided as f64) } else { "-".to_string() };
+        let snakes = totals.snakes.max(1) as f64;
+        println!(
+            "{:<name_width$}  {:>6}  {:>5}  {:>6}  {:>5}  {:>8}  {:>10.1}  {:>9.1}",
+            agent,
+            totals.snakes,
+            totals.wins,
+            totals.losses,
+            totals.draws,
+            win_rate,
+            totals.length as f64 / snakes,
+            totals.score as f64 / snakes
+        );
+    }
+
+    println!();
+    let mut header = format!("{:<name_width$}", "DEATHS");
+    for cause in ALL_DEATH_CAUSES {
+        let _ = write!(header, "  {:>11}", cause.get_name());
+    }
+    println!("{}  {:>11}", header, "ALIVE");
+    for (agent, totals) in agents.iter().zip(&totals) {
+        let mut row = format!("{:<name_width$}", agent);
+        for count in totals.deaths {
+            let _ = write!(row, "  {:>11}", count);
+        }
+        println!("{}  {:>11}", row, totals.survived);
+    }
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let registry = agents::register();
+    if args.iter().any(|arg| arg == "--help" || arg == "-h") {
+        println!("{}", USAGE);
+        return;
+    }
+    if args.iter().any(|arg| arg == "--list") {
+        for name in registry.names() {
+            println!("{}", name);
+        }
+        return;
+    }
+    let options = parse_options(&args, &registry);
+
+    let started = Instant::now();
+    let mut records = Vec::new();
+    for &game_mode in &options.modes {
+        for &(width, height) in &options.sizes {
+            for seed in options.seeds.iter().cloned().flatten() {
+                for agents in pairings(&options.agents, options.player_mode) {
+                    let config = MatchConfig {
+                        width,
+                        height,
+                        game_mode,
+                        player_mode: options.player_mode,
+                        corpse_rule: CorpseRule::Remove,
+                        survivor_rule: SurvivorRule::WinsNow,
+                        input_buffer: 2,
+                        wrap_walls: options.wrap_walls,
+                        goal: None,
+                        seed,
+                    };
+                    records.push(play(config, &agents, &registry, options.max_ticks));
+                }
+            }
+        }
+    }
+
+    println!(
+        "{} matches of {} in {:.1}s\n",
+        records.len(),
+        options.player_mode.get_name(),
+        started.elapsed().as_secs_f64()
+    );
+    print_report(&options.agents, &records);
+
+    match fs::write(&options.csv, to_csv(&records)) {
+        Ok(()) => println!("\nResults written to {}", options.csv),
+        Err(err) => eprintln!("Could not write {}: {}", options.csv, err),
+    }
+}
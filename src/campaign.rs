@@ -0,0 +1,85 @@
+//! The campaign: a fixed run of maps, each cleared by meeting its goal,
+//! and the save file recording how far the player has got.
+//!
+//! Progress is stored as a single `unlocked <stages>` line.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::level::{Level, LEVEL_DIR};
+use crate::mode::Goal;
+
+pub const PROGRESS_FILE: &str = "campaign.txt";
+
+// Board size for stages without a map file
+const OPEN_BOARD_SIZE: i32 = 30;
+
+pub struct Stage {
+    pub map: Option<&'static str>, // File in `LEVEL_DIR`, or the open board
+    pub goal: Goal,
+}
+
+pub const STAGES: [Stage; 8] = [
+    Stage { map: None, goal: Goal::Eat(5) },
+    Stage { map: None, goal: Goal::Length(10) },
+    Stage { map: Some("cross.txt"), goal: Goal::Eat(8) },
+    Stage { map: Some("cross.txt"), goal: Goal::Survive(45) },
+    Stage { map: Some("pillars.txt"), goal: Goal::Eat(10) },
+    Stage { map: Some("rooms.txt"), goal: Goal::Survive(60) },
+    Stage { map: Some("rooms.txt"), goal: Goal::Length(18) },
+    Stage { map: Some("pillars.txt"), goal: Goal::Survive(90) },
+];
+
+impl Stage {
+    pub fn level(&self) -> io::Result<Level> {
+        match self.map {
+            Some(file) => Level::load(&Path::new(LEVEL_DIR).join(file)),
+            None => Ok(Level::open(OPEN_BOARD_SIZE, OPEN_BOARD_SIZE)),
+        }
+    }
+}
+
+/// How many stages are open to play. The first always is.
+pub struct Progress {
+    unlocked: usize,
+}
+
+impl Progress {
+    /// Reads `PROGRESS_FILE`; a missing or malformed file means a fresh start.
+    pub fn load() -> Progress {
+        let text = fs::read_to_string(PROGRESS_FILE).unwrap_or_default();
+        let unlocked = text
+            .lines()
+            .find_map(|line| line.strip_prefix("unlocked "))
+            .and_then(|value| value.trim().parse().ok())
+            .unwrap_or(1);
+        Progress { unlocked: unlocked.clamp(1, STAGES.len() + 1) }
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        fs::write(PROGRESS_FILE, format!("unlocked {}\n", self.unlocked))
+    }
+
+    pub fn is_unlocked(&self, stage: usize) -> bool {
+        stage < self.unlocked
+    }
+
+    /// Whether `stage` has been beaten: the stage after it is open, or it
+    /// is the last one and was cleared.
+    pub fn is_cleared(&self, stage: usize) -> bool {
+        stage + 1 < self.unlocked || self.unlocked > STAGES.len()
+    }
+
+    /// Records `stage` as cleared, opening the next one. Returns whether
+    /// anything changed.
+    pub fn clear(&mut self, stage: usize) -> bool {
+        let unlocked = (stage + 2).min(STAGES.len() + 1);
+        if unlocked <= self.unlocked {
+            return false;
+        }
+        self.unlocked = unlocked;
+        true
+    }
+}
+
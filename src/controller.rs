@@ -0,0 +1,185 @@
+//! Snake agents. A `SnakeController` steers one snake: each tick it is
+//! shown the board through an `Observation` and answers with the way its
+//! snake should go. The `Registry` names the controllers a match can be set
+//! up with, the built-in computer players among them.
+
+use crate::ai::{choose_direction, Difficulty, ALL_DIFFICULTIES};
+use crate::enemy::Enemy;
+use crate::food::Food;
+use crate::sim::Simulation;
+use crate::snake::{Direction, Snake};
+
+/// Steers one snake.
+pub trait SnakeController {
+    /// The way the snake should go next. Answering with its current heading
+    /// keeps it going straight, and so does turning straight back.
+    fn choose(&mut self, observation: &Observation) -> Direction;
+}
+
+/// The turn `controller` makes for `player` this tick, if any, to be
+/// passed in `TickInputs`. Controllers are asked only while their snake is
+/// alive and has made its last turn, and only a change of heading counts.
+pub fn next_turn(controller: &mut dyn SnakeController, sim: &Simulation, player: usize) -> Option<Direction> {
+    if !sim.is_alive(player) || sim.queued_turns(player) > 0 {
+        return None;
+    }
+    let direction = controller.choose(&Observation::new(sim, player));
+    (direction != sim.snakes()[player].head_direction()).then_some(direction)
+}
+
+/// What a controller can see of the match on its turn. Nothing in here can
+/// change the match.
+pub struct Observation<'a> {
+    sim: &'a Simulation,
+    player: usize,
+}
+
+impl<'a> Observation<'a> {
+    pub fn new(sim: &'a Simulation, player: usize) -> Observation<'a> {
+        Observation { sim, player }
+    }
+
+    /// Index of the snake being steered.
+    pub fn player(&self) -> usize {
+        self.player
+    }
+
+    /// Ticks since the match started, `TICK_RATE` to the second.
+    pub fn tick(&self) -> u64 {
+        self.sim.tick()
+    }
+
+    pub fn width(&self) -> i32 {
+        self.sim.width()
+    }
+
+    pub fn height(&self) -> i32 {
+        self.sim.height()
+    }
+
+    /// Whether the edges lead round to the opposite side.
+    pub fn wraps(&self) -> bool {
+        self.sim.config().wrap_walls
+    }
+
+    pub fn head(&self) -> (i32, i32) {
+        self.sim.snakes()[self.player].head_position()
+    }
+
+    pub fn heading(&self) -> Direction {
+        self.sim.snakes()[self.player].head_direction()
+    }
+
+    /// Cells of the controller's own snake, head first.
+    pub fn body(&self) -> impl Iterator<Item = (i32, i32)> + '_ {
+        self.sim.snakes()[self.player].body()
+    }
+
+    pub fn score(&self) -> i32 {
+        self.sim.scores()[self.player]
+    }
+
+    /// Every other living snake, with its player index.
+    pub fn others(&self) -> impl Iterator<Item = (usize, &Snake)> + '_ {
+        self.sim.snakes()
+            .iter()
+            .enumerate()
+            .filter(|&(other, _)| other != self.player && self.sim.is_alive(other))
+    }
+
+    pub fn foods(&self) -> &[Food] {
+        self.sim.foods()
+    }
+
+    pub fn enemies(&self) -> &[Enemy] {
+        self.sim.enemies()
+    }
+
+    /// Positions of every inner wall. The border is not counted.
+    pub fn walls(&self) -> impl Iterator<Item = (i32, i32)> + '_ {
+        self.sim.map().walls()
+    }
+
+    /// Cells left blocked by dead snakes.
+    pub fn obstacles(&self) -> &[(i32, i32)] {
+        self.sim.obstacles()
+    }
+
+    /// Whether (x, y) is the border or an inner wall.
+    pub fn is_wall(&self, x: i32, y: i32) -> bool {
+        !self.sim.map().is_inside(x, y) || self.sim.map().is_wall(x, y)
+    }
+
+    /// The whole match, for controllers that want to look further, e.g.
+    /// through `pathfind::Grid::from_sim`.
+    pub fn sim(&self) -> &Simulation {
+        self.sim
+    }
+}
+
+/// A built-in computer player as a controller.
+pub struct Bot {
+    difficulty: Difficulty,
+}
+
+impl Bot {
+    pub fn new(difficulty: Difficulty) -> Bot {
+        Bot { difficulty }
+    }
+}
+
+impl SnakeController for Bot {
+    fn choose(&mut self, observation: &Observation) -> Direction {
+        choose_direction(observation.sim(), observation.player(), self.difficulty).unwrap_or(observation.heading())
+    }
+}
+
+type Factory = Box<dyn Fn() -> Box<dyn SnakeController>>;
+
+/// Controllers by name, in the order they were registered.
+pub struct Registry {
+    entries: Vec<(String, Factory)>,
+}
+
+impl Default for Registry {
+    /// A registry holding the built-in bots, one per difficulty.
+    fn default() -> Registry {
+        let mut registry = Registry::empty();
+        for difficulty in ALL_DIFFICULTIES {
+            registry.register(difficulty.get_name(), move || Box::new(Bot::new(difficulty)));
+        }
+        registry
+    }
+}
+
+impl Registry {
+    pub fn empty() -> Registry {
+        Registry { entries: Vec::new() }
+    }
+
+    /// Adds a controller under `name`, replacing any already going by it.
+    /// `factory` makes a fresh one for every snake it steers.
+    pub fn register(&mut self, name: &str, factory: impl Fn() -> Box<dyn SnakeController> + 'static) {
+        match self.entries.iter_mut().find(|(entry, _)| entry == name) {
+            Some(entry) => entry.1 = Box::new(factory),
+            None => self.entries.push((name.to_string(), Box::new(factory))),
+        }
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> + '_ {
+        self.entries.iter().map(|(name, _)| name.as_str())
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// A new controller of the kind registered as `name`.
+    pub fn create(&self, name: &str) -> Option<Box<dyn SnakeController>> {
+        self.entries.iter().find(|(entry, _)| entry == name).map(|(_, factory)| factory())
+    }
+}
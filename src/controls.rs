@@ -0,0 +1,183 @@
+//! Keyboard bindings for each player's actions, and which device each
+//! player steers with.
+
+use piston_window::Key;
+
+use crate::snake::Direction;
+
+/// Players that can share the keyboard.
+pub const KEYBOARD_PLAYERS: usize = 2;
+
+/// What a player steers with. Keyboard bindings work either way; a gamepad
+/// only steers the player it is assigned to.
+#[derive(Copy, Clone, PartialEq)]
+pub enum InputDevice {
+    Keyboard,
+    Gamepad(u32), // Controller id as reported by piston
+}
+
+/// Gamepads the controls screen cycles through.
+pub const MAX_GAMEPADS: u32 = 4;
+
+impl InputDevice {
+    pub fn get_name(&self) -> String {
+        match self {
+            InputDevice::Keyboard => "KEYBOARD".to_string(),
+            InputDevice::Gamepad(id) => format!("GAMEPAD {}", id + 1),
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<InputDevice> {
+        if name == "KEYBOARD" {
+            return Some(InputDevice::Keyboard);
+        }
+        let number: u32 = name.strip_prefix("GAMEPAD ")?.parse().ok()?;
+        (1..=MAX_GAMEPADS).contains(&number).then(|| InputDevice::Gamepad(number - 1))
+    }
+
+    /// The next choice on the controls screen.
+    pub fn next(&self) -> InputDevice {
+        match *self {
+            InputDevice::Keyboard => InputDevice::Gamepad(0),
+            InputDevice::Gamepad(id) if id + 1 < MAX_GAMEPADS => InputDevice::Gamepad(id + 1),
+            InputDevice::Gamepad(_) => InputDevice::Keyboard,
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq)]
+pub enum Action {
+    Up,
+    Down,
+    Left,
+    Right,
+    Pause,
+    Confirm,
+}
+
+pub const ALL_ACTIONS: [Action; 6] = [
+    Action::Up,
+    Action::Down,
+    Action::Left,
+    Action::Right,
+    Action::Pause,
+    Action::Confirm,
+];
+
+impl Action {
+    pub fn get_name(&self) -> &str {
+        match self {
+            Action::Up => "UP",
+            Action::Down => "DOWN",
+            Action::Left => "LEFT",
+            Action::Right => "RIGHT",
+            Action::Pause => "PAUSE",
+            Action::Confirm => "CONFIRM",
+        }
+    }
+
+    /// The direction this action steers a snake in, if it is a move.
+    pub fn direction(&self) -> Option<Direction> {
+        match self {
+            Action::Up => Some(Direction::Up),
+            Action::Down => Some(Direction::Down),
+            Action::Left => Some(Direction::Left),
+            Action::Right => Some(Direction::Right),
+            Action::Pause | Action::Confirm => None,
+        }
+    }
+
+    fn index(&self) -> usize {
+        ALL_ACTIONS.iter().position(|action| action == self).unwrap()
+    }
+}
+
+// Keys that can be bound. Escape and Backspace stay reserved for leaving
+// screens so a bad binding can never lock anyone out of the menus.
+const BINDABLE_KEYS: [Key; 58] = [
+    Key::A, Key::B, Key::C, Key::D, Key::E, Key::F, Key::G, Key::H, Key::I,
+    Key::J, Key::K, Key::L, Key::M, Key::N, Key::O, Key::P, Key::Q, Key::R,
+    Key::S, Key::T, Key::U, Key::V, Key::W, Key::X, Key::Y, Key::Z,
+    Key::D0, Key::D1, Key::D2, Key::D3, Key::D4, Key::D5, Key::D6, Key::D7, Key::D8, Key::D9,
+    Key::Up, Key::Down, Key::Left, Key::Right,
+    Key::Space, Key::Return, Key::Tab,
+    Key::LShift, Key::RShift, Key::LCtrl, Key::RCtrl,
+    Key::Comma, Key::Period, Key::Slash, Key::Semicolon, Key::Minus, Key::Equals,
+    Key::NumPad2, Key::NumPad4, Key::NumPad5, Key::NumPad6, Key::NumPad8,
+];
+
+pub fn is_bindable(key: Key) -> bool {
+    BINDABLE_KEYS.contains(&key)
+}
+
+/// Name used in the config file and on screen, e.g. `UP` or `NUMPAD8`.
+pub fn key_name(key: Key) -> String {
+    format!("{:?}", key).to_uppercase()
+}
+
+pub fn key_from_name(name: &str) -> Option<Key> {
+    BINDABLE_KEYS.iter().copied().find(|key| key_name(*key) == name)
+}
+
+#[derive(Clone, PartialEq)]
+pub struct Controls {
+    bindings: [[Key; ALL_ACTIONS.len()]; KEYBOARD_PLAYERS],
+}
+
+impl Default for Controls {
+    fn default() -> Controls {
+        Controls {
+            bindings: [
+                // Player 1: arrow keys
+                [Key::Up, Key::Down, Key::Left, Key::Right, Key::P, Key::Return],
+                // Player 2: WASD
+                [Key::W, Key::S, Key::A, Key::D, Key::Q, Key::E],
+            ],
+        }
+    }
+}
+
+impl Controls {
+    pub fn key(&self, player: usize, action: Action) -> Key {
+        self.bindings[player][action.index()]
+    }
+
+    pub fn bind(&mut self, player: usize, action: Action, key: Key) {
+        self.bindings[player][action.index()] = key;
+    }
+
+    /// The player and action `key` is bound to. With a conflict the first
+    /// binding found wins.
+    pub fn action(&self, key: Key) -> Option<(usize, Action)> {
+        for (player, keys) in self.bindings.iter().enumerate() {
+            if let Some(index) = keys.iter().position(|bound| *bound == key) {
+                return Some((player, ALL_ACTIONS[index]));
+            }
+        }
+        None
+    }
+
+    /// Menu navigation for `key`: any player's bindings, plus the arrow keys
+    /// and Return so the menus stay usable whatever is bound.
+    pub fn menu_action(&self, key: Key) -> Option<Action> {
+        match key {
+            Key::Up => Some(Action::Up),
+            Key::Down => Some(Action::Down),
+            Key::Left => Some(Action::Left),
+            Key::Right => Some(Action::Right),
+            Key::Return => Some(Action::Confirm),
+            _ => self.action(key).map(|(_, action)| action),
+        }
+    }
+
+    /// Whether the key for `player`'s `action` is also bound to another action.
+    pub fn has_conflict(&self, player: usize, action: Action) -> bool {
+        let key = self.key(player, action);
+        self.bindings
+            .iter()
+            .flatten()
+            .filter(|bound| **bound == key)
+            .count()
+            > 1
+    }
+}
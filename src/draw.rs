@@ -1,94 +1,381 @@
-use piston_window::{ellipse, rectangle, Context, G2d};
-use piston_window::types::Color;
+use piston_window::{ellipse, polygon, rectangle, Context, G2d, Transformed};
+use piston_window::types::{Color, Matrix2d};
 
-const BLOCK_SIZE: f64 = 25.0;
+use crate::snake::Snake;
+use crate::enemy::{Enemy, EnemyKind};
+use crate::food::{Food, FoodKind};
+use crate::powerup::{PowerUp, PowerUpKind};
+use crate::font::{glyph, text_width, GLYPH_ADVANCE, GLYPH_HEIGHT, GLYPH_WIDTH};
 
-pub fn to_coord(game_coord: i32) -> f64 {
-    (game_coord as f64) * BLOCK_SIZE
+/// Block size in pixels for a window opened at its natural size.
+pub const DEFAULT_BLOCK_SIZE: f64 = 25.0;
+
+// Glyph pixel sizes, in blocks
+pub const TEXT_SMALL: f64 = 0.15;
+pub const TEXT_NORMAL: f64 = 0.2;
+pub const TEXT_LARGE: f64 = 0.45;
+
+// (body, head) colours per player slot
+const SNAKE_COLORS: [(Color, Color); 4] = [
+    ([0.2, 0.7, 0.2, 1.0], [0.3, 0.9, 0.3, 1.0]), // Green for player 1
+    ([0.2, 0.2, 0.7, 1.0], [0.3, 0.3, 0.9, 1.0]), // Blue for player 2
+    ([0.7, 0.2, 0.5, 1.0], [0.9, 0.3, 0.7, 1.0]), // Magenta for player 3
+    ([0.6, 0.6, 0.6, 1.0], [0.8, 0.8, 0.8, 1.0]), // Grey for player 4
+];
+
+const ENEMY_COLOR: Color = [0.8, 0.0, 0.0, 1.0]; // Red enemy
+const PATROLLER_COLOR: Color = [0.1, 0.3, 0.9, 1.0]; // Blue
+const WALL_HUGGER_COLOR: Color = [0.4, 0.5, 0.3, 1.0]; // Olive
+const WALL_HUGGER_SHELL_COLOR: Color = [0.25, 0.3, 0.2, 1.0];
+const THIEF_COLOR: Color = [0.5, 0.2, 0.6, 1.0]; // Purple
+const THIEF_MASK_COLOR: Color = [0.05, 0.05, 0.05, 1.0];
+const CHASER_COLOR: Color = [1.0, 0.5, 0.0, 1.0]; // Orange
+const ENEMY_EYE_COLOR: Color = [1.0, 1.0, 1.0, 1.0]; // White eyes
+const POWER_UP_LETTER_COLOR: Color = [0.1, 0.1, 0.1, 1.0];
+const TIMER_BAR_COLOR: Color = [1.0, 1.0, 1.0, 0.8];
+
+// Block size and (x, y) margins that fit a `columns` x `rows` grid into a
+// window of `window_size` pixels with square cells, centred
+fn fit(columns: i32, rows: i32, window_size: [f64; 2]) -> (f64, f64, f64) {
+    let [window_width, window_height] = window_size;
+    let size = (window_width / columns as f64).min(window_height / rows as f64);
+    let margin_x = (window_width - size * columns as f64) / 2.0;
+    let margin_y = (window_height - size * rows as f64) / 2.0;
+    (size, margin_x, margin_y)
 }
 
-pub fn to_coord_u32(game_coord: i32) -> u32 {
-    to_coord(game_coord) as u32
+/// Where a grid sits in the window and how big its blocks are, as worked
+/// out by `fit_to_window` for each frame.
+#[derive(Clone, Copy)]
+pub struct Layout {
+    transform: Matrix2d,
+    block_size: f64,
 }
 
-pub fn draw_block(color: Color, x: i32, y: i32, con: &Context, g: &mut G2d) {
-    let gui_x = to_coord(x);
-    let gui_y = to_coord(y);
+impl Layout {
+    /// Pixel offset of grid column or row `game_coord` from the grid's corner.
+    pub fn to_coord(&self, game_coord: i32) -> f64 {
+        (game_coord as f64) * self.block_size
+    }
+}
+
+/// Sizes blocks so a `columns` x `rows` grid fills as much of the window
+/// as it can with square cells, with the grid in the middle of it.
+pub fn fit_to_window(columns: i32, rows: i32, con: &Context) -> Layout {
+    let (size, margin_x, margin_y) = fit(columns, rows, con.get_view_size());
+    Layout {
+        transform: con.trans(margin_x, margin_y).transform,
+        block_size: size,
+    }
+}
+
+/// The grid cell under `position` in a window laid out by `fit_to_window`.
+pub fn window_to_cell(position: [f64; 2], window_size: [f64; 2], columns: i32, rows: i32) -> (i32, i32) {
+    let (size, margin_x, margin_y) = fit(columns, rows, window_size);
+    (
+        ((position[0] - margin_x) / size).floor() as i32,
+        ((position[1] - margin_y) / size).floor() as i32,
+    )
+}
+
+pub fn draw_block(color: Color, x: i32, y: i32, layout: &Layout, g: &mut G2d) {
+    let gui_x = layout.to_coord(x);
+    let gui_y = layout.to_coord(y);
 
     rectangle(
         color,
-        [gui_x, gui_y, BLOCK_SIZE, BLOCK_SIZE],
-        con.transform,
+        [gui_x, gui_y, layout.block_size, layout.block_size],
+        layout.transform,
         g,
     )
 }
 
-pub fn draw_circle(color: Color, x: i32, y: i32, con: &Context, g: &mut G2d) {
-    let gui_x = to_coord(x);
-    let gui_y = to_coord(y);
+pub fn draw_circle(color: Color, x: i32, y: i32, layout: &Layout, g: &mut G2d) {
+    let gui_x = layout.to_coord(x);
+    let gui_y = layout.to_coord(y);
 
     ellipse(
         color,
-        [gui_x, gui_y, BLOCK_SIZE, BLOCK_SIZE],
-        con.transform,
+        [gui_x, gui_y, layout.block_size, layout.block_size],
+        layout.transform,
         g,
     )
 }
 
-pub fn draw_rectangle(color: Color, x: i32, y: i32, width: i32, height: i32, con: &Context, g: &mut G2d) {
-    let x = to_coord(x);
-    let y = to_coord(y);
+pub fn draw_rectangle(color: Color, x: i32, y: i32, width: i32, height: i32, layout: &Layout, g: &mut G2d) {
+    let x = layout.to_coord(x);
+    let y = layout.to_coord(y);
 
     rectangle(
         color,
         [x,
         y,
-        BLOCK_SIZE * (width as f64),
-        BLOCK_SIZE * (height as f64)],
-        con.transform,
+        layout.block_size * (width as f64),
+        layout.block_size * (height as f64)],
+        layout.transform,
         g,
     )
 }
 
-pub fn draw_apple(x: i32, y: i32, con: &Context, g: &mut G2d) {
-    let gui_x = to_coord(x);
-    let gui_y = to_coord(y);
-    
+pub fn draw_apple(x: i32, y: i32, layout: &Layout, g: &mut G2d) {
     // Apple body - red with slight gradient effect
-    let apple_red: Color = [0.8, 0.1, 0.1, 1.0];
-    let apple_highlight: Color = [0.9, 0.3, 0.2, 1.0];
-    
-    // Draw main apple body
+    draw_fruit([0.8, 0.1, 0.1, 1.0], [0.9, 0.3, 0.2, 1.0], x, y, layout, g);
+}
+
+/// Draws any kind of food, blinking it out now and then when it is about
+/// to disappear.
+pub fn draw_food(food: &Food, layout: &Layout, g: &mut G2d) {
+    if food.blinked_out() {
+        return;
+    }
+    let (x, y) = food.position();
+    match food.kind {
+        FoodKind::Apple => draw_apple(x, y, layout, g),
+        FoodKind::Golden => draw_fruit([0.9, 0.7, 0.1, 1.0], [1.0, 0.95, 0.6, 1.0], x, y, layout, g),
+        FoodKind::Berry => draw_berry(x, y, layout, g),
+        FoodKind::Speed => {
+            draw_fruit([0.1, 0.7, 0.9, 1.0], [0.6, 0.9, 1.0, 1.0], x, y, layout, g);
+            draw_streaks(x, y, layout, g);
+        }
+        FoodKind::Slow => draw_fruit([0.3, 0.25, 0.6, 1.0], [0.5, 0.45, 0.8, 1.0], x, y, layout, g),
+    }
+}
+
+fn power_up_color(kind: PowerUpKind) -> Color {
+    match kind {
+        PowerUpKind::Multiplier => [1.0, 0.0, 1.0, 1.0], // Magenta
+        PowerUpKind::Invincible => [1.0, 0.85, 0.2, 1.0], // Gold
+        PowerUpKind::Ghost => [0.85, 0.85, 1.0, 1.0],     // Pale blue
+        PowerUpKind::SlowMotion => [0.3, 0.8, 1.0, 1.0],  // Sky blue
+        PowerUpKind::Magnet => [1.0, 0.3, 0.3, 1.0],      // Red
+    }
+}
+
+/// Draws a power-up lying on the board: a coin in its colour marked with
+/// its letter, blinking when about to disappear.
+pub fn draw_power_up(item: &PowerUp, layout: &Layout, g: &mut G2d) {
+    if item.blinked_out() {
+        return;
+    }
+    let (x, y) = item.position();
+    draw_power_up_icon(item.kind, item.kind.letter(), x as f64, y as f64, None, layout, g);
+}
+
+/// Draws a one-block power-up icon with its top-left corner at (x, y), in
+/// blocks, marked with `label`. With `time_left` (0 to 1) a bar underneath
+/// shows how much of the power-up is left.
+pub fn draw_power_up_icon(
+    kind: PowerUpKind,
+    label: &str,
+    x: f64,
+    y: f64,
+    time_left: Option<f64>,
+    layout: &Layout,
+    g: &mut G2d,
+) {
+    let gui_x = x * layout.block_size;
+    let gui_y = y * layout.block_size;
+
+    ellipse(power_up_color(kind), [gui_x, gui_y, layout.block_size, layout.block_size], layout.transform, g);
+    draw_text_centered(POWER_UP_LETTER_COLOR, label, x + 0.5, y + 0.15, 0.1, layout, g);
+
+    if let Some(time_left) = time_left {
+        rectangle(
+            TIMER_BAR_COLOR,
+            [gui_x, gui_y + layout.block_size * 1.1, layout.block_size * time_left.clamp(0.0, 1.0), layout.block_size * 0.15],
+            layout.transform,
+            g,
+        );
+    }
+}
+
+// An apple-shaped fruit in the given body and highlight colours
+fn draw_fruit(body: Color, highlight: Color, x: i32, y: i32, layout: &Layout, g: &mut G2d) {
+    let gui_x = layout.to_coord(x);
+    let gui_y = layout.to_coord(y);
+
+    // Draw main body
     ellipse(
-        apple_red,
-        [gui_x, gui_y, BLOCK_SIZE * 0.9, BLOCK_SIZE * 0.9],
-        con.transform,
+        body,
+        [gui_x, gui_y, layout.block_size * 0.9, layout.block_size * 0.9],
+        layout.transform,
         g,
     );
-    
-    // Draw highlight on apple
+
+    // Draw highlight
     ellipse(
-        apple_highlight,
-        [gui_x + BLOCK_SIZE * 0.2, gui_y + BLOCK_SIZE * 0.2, BLOCK_SIZE * 0.3, BLOCK_SIZE * 0.3],
-        con.transform,
+        highlight,
+        [gui_x + layout.block_size * 0.2, gui_y + layout.block_size * 0.2, layout.block_size * 0.3, layout.block_size * 0.3],
+        layout.transform,
         g,
     );
-    
+
     // Draw stem (brown rectangle)
     let stem_color: Color = [0.4, 0.2, 0.1, 1.0];
     rectangle(
         stem_color,
-        [gui_x + BLOCK_SIZE * 0.45, gui_y - BLOCK_SIZE * 0.1, BLOCK_SIZE * 0.1, BLOCK_SIZE * 0.15],
-        con.transform,
+        [gui_x + layout.block_size * 0.45, gui_y - layout.block_size * 0.1, layout.block_size * 0.1, layout.block_size * 0.15],
+        layout.transform,
         g,
     );
-    
+
     // Draw leaf (green ellipse)
     let leaf_color: Color = [0.2, 0.7, 0.2, 1.0];
     ellipse(
         leaf_color,
-        [gui_x + BLOCK_SIZE * 0.6, gui_y - BLOCK_SIZE * 0.05, BLOCK_SIZE * 0.2, BLOCK_SIZE * 0.15],
-        con.transform,
+        [gui_x + layout.block_size * 0.6, gui_y - layout.block_size * 0.05, layout.block_size * 0.2, layout.block_size * 0.15],
+        layout.transform,
         g,
     );
 }
+
+// Three small purple berries in a cluster
+fn draw_berry(x: i32, y: i32, layout: &Layout, g: &mut G2d) {
+    let gui_x = layout.to_coord(x);
+    let gui_y = layout.to_coord(y);
+    let berry_color: Color = [0.5, 0.1, 0.6, 1.0];
+    let size = layout.block_size * 0.45;
+
+    for (dx, dy) in [(0.05, 0.45), (0.5, 0.45), (0.28, 0.1)] {
+        ellipse(
+            berry_color,
+            [gui_x + layout.block_size * dx, gui_y + layout.block_size * dy, size, size],
+            layout.transform,
+            g,
+        );
+    }
+}
+
+// White speed lines across the left of a fruit
+fn draw_streaks(x: i32, y: i32, layout: &Layout, g: &mut G2d) {
+    let gui_x = layout.to_coord(x);
+    let gui_y = layout.to_coord(y);
+    let streak_color: Color = [1.0, 1.0, 1.0, 1.0];
+
+    for dy in [0.35, 0.6] {
+        rectangle(
+            streak_color,
+            [gui_x - layout.block_size * 0.1, gui_y + layout.block_size * dy, layout.block_size * 0.35, layout.block_size * 0.08],
+            layout.transform,
+            g,
+        );
+    }
+}
+
+pub fn draw_snake(snake: &Snake, player: usize, layout: &Layout, g: &mut G2d) {
+    let (body_color, head_color) = SNAKE_COLORS[player % SNAKE_COLORS.len()];
+
+    let mut is_head = true;
+    for (x, y) in snake.body() {
+        if is_head {
+            // Draw head larger and brighter
+            draw_circle(head_color, x, y, layout, g);
+            // Draw eyes on head
+            let eye_color: Color = [0.0, 0.0, 0.0, 1.0];
+            let gui_x = layout.to_coord(x);
+            let gui_y = layout.to_coord(y);
+            let (eye, eye_size) = (layout.block_size * 0.24, layout.block_size * 0.16);
+            ellipse(
+                eye_color,
+                [gui_x + eye, gui_y + eye, eye_size, eye_size],
+                layout.transform,
+                g,
+            );
+            ellipse(
+                eye_color,
+                [gui_x + layout.block_size * 0.6, gui_y + eye, eye_size, eye_size],
+                layout.transform,
+                g,
+            );
+            is_head = false;
+        } else {
+            // Draw body segments as circles
+            draw_circle(body_color, x, y, layout, g);
+        }
+    }
+}
+
+/// Draws an enemy in its kind's colour and shape: round wanderers, square
+/// patrollers, shelled wall-huggers, masked thieves and arrowhead chasers
+/// pointing the way they are going.
+pub fn draw_enemy(enemy: &Enemy, layout: &Layout, g: &mut G2d) {
+    let (x, y) = enemy.position();
+    let gui_x = layout.to_coord(x);
+    let gui_y = layout.to_coord(y);
+    let size = layout.block_size;
+
+    match enemy.kind() {
+        EnemyKind::Wanderer => draw_circle(ENEMY_COLOR, x, y, layout, g),
+        EnemyKind::Patroller => {
+            rectangle(PATROLLER_COLOR, [gui_x + size * 0.05, gui_y + size * 0.05, size * 0.9, size * 0.9], layout.transform, g);
+        }
+        EnemyKind::WallHugger => {
+            draw_block(WALL_HUGGER_COLOR, x, y, layout, g);
+            rectangle(WALL_HUGGER_SHELL_COLOR, [gui_x + size * 0.2, gui_y + size * 0.45, size * 0.6, size * 0.4], layout.transform, g);
+        }
+        EnemyKind::Thief => {
+            draw_circle(THIEF_COLOR, x, y, layout, g);
+            // Mask across the eyes
+            rectangle(THIEF_MASK_COLOR, [gui_x + size * 0.1, gui_y + size * 0.18, size * 0.8, size * 0.28], layout.transform, g);
+        }
+        EnemyKind::Chaser => {
+            // Tip at the middle of the side it is heading for
+            let (dx, dy) = enemy.direction().offset();
+            let (center_x, center_y) = (gui_x + size * 0.5, gui_y + size * 0.5);
+            let (half, side_x, side_y) = (size * 0.5, -dy as f64, dx as f64);
+            let tip = [center_x + dx as f64 * half, center_y + dy as f64 * half];
+            let back_x = center_x - dx as f64 * half;
+            let back_y = center_y - dy as f64 * half;
+            let corners = [tip, [back_x + side_x * half, back_y + side_y * half], [back_x - side_x * half, back_y - side_y * half]];
+            polygon(CHASER_COLOR, &corners, layout.transform, g);
+            return; // No eyes on chasers
+        }
+    }
+
+    // Draw eyes
+    let (eye, eye_size) = (size * 0.24, size * 0.16);
+    ellipse(
+        ENEMY_EYE_COLOR,
+        [gui_x + eye, gui_y + eye, eye_size, eye_size],
+        layout.transform,
+        g,
+    );
+    ellipse(
+        ENEMY_EYE_COLOR,
+        [gui_x + size * 0.6, gui_y + eye, eye_size, eye_size],
+        layout.transform,
+        g,
+    );
+}
+
+/// Draws `text` with its top-left corner at (x, y), in blocks. Each font
+/// pixel is `pixel` blocks square, so `TEXT_NORMAL` makes a letter one
+/// block wide.
+pub fn draw_text(color: Color, text: &str, x: f64, y: f64, pixel: f64, layout: &Layout, g: &mut G2d) {
+    let size = pixel * layout.block_size;
+    let mut gui_x = x * layout.block_size;
+    let gui_y = y * layout.block_size;
+
+    for c in text.chars() {
+        if let Some(rows) = glyph(c) {
+            for (row, bits) in rows.iter().enumerate().take(GLYPH_HEIGHT as usize) {
+                for col in 0..GLYPH_WIDTH {
+                    if bits & (1 << (GLYPH_WIDTH - 1 - col)) != 0 {
+                        rectangle(
+                            color,
+                            [gui_x + col as f64 * size, gui_y + row as f64 * size, size, size],
+                            layout.transform,
+                            g,
+                        );
+                    }
+                }
+            }
+        }
+        gui_x += GLYPH_ADVANCE as f64 * size;
+    }
+}
+
+/// Draws `text` horizontally centred on `center_x`.
+pub fn draw_text_centered(color: Color, text: &str, center_x: f64, y: f64, pixel: f64, layout: &Layout, g: &mut G2d) {
+    draw_text(color, text, center_x - text_width(text, pixel) / 2.0, y, pixel, layout, g);
+}
@@ -0,0 +1,405 @@
+//! Level editor: paints walls, starting spots and zones onto a `Level`,
+//! checks the result is playable and saves it to `LEVEL_DIR`.
+
+use std::collections::VecDeque;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use piston_window::G2d;
+use piston_window::types::Color;
+
+use crate::draw::{draw_block, Layout, draw_rectangle, draw_snake, draw_text, TEXT_NORMAL, TEXT_SMALL};
+use crate::game::BORDER_COLOR;
+use crate::level::{Level, Zone, LEVEL_DIR};
+use crate::snake::{Direction, Snake};
+
+const ENEMY_ZONE_COLOR: Color = [0.8, 0.0, 0.0, 0.3];
+const NO_FOOD_ZONE_COLOR: Color = [0.9, 0.8, 0.0, 0.3];
+const NEW_ZONE_COLOR: Color = [1.0, 1.0, 1.0, 0.2];
+const CURSOR_COLOR: Color = [1.0, 1.0, 1.0, 0.5];
+const ERROR_COLOR: Color = [1.0, 0.2, 0.2, 1.0];
+const OK_COLOR: Color = [0.3, 1.0, 0.3, 1.0];
+
+// Open cells food must be able to reach for a level to pass
+const MIN_FOOD_CELLS: usize = 10;
+
+#[derive(Copy, Clone, PartialEq)]
+enum Tool {
+    Wall,
+    Spawn(usize), // Player index
+    EnemyZone,
+    NoFoodZone,
+}
+
+const TOOLS: [Tool; 7] = [
+    Tool::Wall,
+    Tool::Spawn(0),
+    Tool::Spawn(1),
+    Tool::Spawn(2),
+    Tool::Spawn(3),
+    Tool::EnemyZone,
+    Tool::NoFoodZone,
+];
+
+impl Tool {
+    fn get_name(&self) -> String {
+        match self {
+            Tool::Wall => "WALL".to_string(),
+            Tool::Spawn(player) => format!("P{} START", player + 1),
+            Tool::EnemyZone => "ENEMY ZONE".to_string(),
+            Tool::NoFoodZone => "NO FOOD".to_string(),
+        }
+    }
+}
+
+pub struct Editor {
+    level: Level,
+    path: Option<PathBuf>, // File the level is saved to; picked on the first save of a new one
+    cursor: (i32, i32),
+    tool: usize, // Index into `TOOLS`
+    zone_corner: Option<(i32, i32)>, // First corner of the zone being placed
+    painting: Option<bool>, // Wall state a held mouse button paints
+    message: Option<(String, Color)>,
+}
+
+impl Editor {
+    pub fn new(level: Level, path: Option<PathBuf>) -> Editor {
+        let cursor = (level.width() / 2, level.height() / 2);
+        Editor {
+            level,
+            path,
+            cursor,
+            tool: 0,
+            zone_corner: None,
+            painting: None,
+            message: None,
+        }
+    }
+
+    pub fn level(&self) -> &Level {
+        &self.level
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    pub fn move_cursor(&mut self, dx: i32, dy: i32) {
+        let (x, y) = self.cursor;
+        if self.level.is_inside(x + dx, y + dy) {
+            self.cursor = (x + dx, y + dy);
+        }
+    }
+
+    /// Moves the cursor to the cell under the mouse, painting it while a
+    /// button is held.
+    pub fn point_at(&mut self, x: i32, y: i32) {
+        if !self.level.is_inside(x, y) || self.cursor == (x, y) {
+            return;
+        }
+        self.cursor = (x, y);
+        if let Some(wall) = self.painting {
+            self.level.set_wall(x, y, wall);
+        }
+    }
+
+    pub fn next_tool(&mut self) {
+        self.tool = (self.tool + 1) % TOOLS.len();
+        self.zone_corner = None;
+    }
+
+    /// Uses the current tool at the cursor: toggles a wall, places a start
+    /// (turning it if it is already there), or marks a zone corner.
+    pub fn apply(&mut self) {
+        let (x, y) = self.cursor;
+        self.message = None;
+        match TOOLS[self.tool] {
+            Tool::Wall => self.level.set_wall(x, y, !self.level.is_wall(x, y)),
+            Tool::Spawn(player) => {
+                let spawn = &mut self.level.spawns[player];
+                if (spawn.x, spawn.y) == (x, y) {
+                    spawn.direction = turn_right(spawn.direction);
+                } else {
+                    (spawn.x, spawn.y) = (x, y);
+                }
+            }
+            Tool::EnemyZone | Tool::NoFoodZone => {
+                let Some((corner_x, corner_y)) = self.zone_corner.take() else {
+                    self.zone_corner = Some(self.cursor);
+                    return;
+                };
+                let zone = Zone {
+                    x: corner_x.min(x),
+                    y: corner_y.min(y),
+                    width: (corner_x - x).abs() + 1,
+                    height: (corner_y - y).abs() + 1,
+                };
+                if TOOLS[self.tool] == Tool::EnemyZone {
+                    self.level.enemy_zones.push(zone);
+                } else {
+                    self.level.no_food_zones.push(zone);
+                }
+            }
+        }
+    }
+
+    /// Clears the wall and any zones under the cursor.
+    pub fn erase(&mut self) {
+        let (x, y) = self.cursor;
+        self.message = None;
+        self.zone_corner = None;
+        self.level.set_wall(x, y, false);
+        self.level.enemy_zones.retain(|zone| !zone.contains(x, y));
+        self.level.no_food_zones.retain(|zone| !zone.contains(x, y));
+    }
+
+    /// A mouse button went down: the right one erases, the left one uses
+    /// the tool. With the wall tool, dragging keeps painting.
+    pub fn start_painting(&mut self, erase: bool) {
+        if erase {
+            self.erase();
+            self.painting = Some(false);
+        } else {
+            self.apply();
+            if TOOLS[self.tool] == Tool::Wall {
+                self.painting = Some(self.level.is_wall(self.cursor.0, self.cursor.1));
+            }
+        }
+    }
+
+    pub fn stop_painting(&mut self) {
+        self.painting = None;
+    }
+
+    /// Checks the level can be played, showing what is wrong if not.
+    pub fn validate(&mut self) -> bool {
+        match validate(&self.level) {
+            Ok(()) => true,
+            Err(message) => {
+                self.message = Some((message, ERROR_COLOR));
+                false
+            }
+        }
+    }
+
+    /// Saves a valid level. Only the `custom-<n>.txt` files the editor made
+    /// are written over; new maps and edits of the maps that ship with the
+    /// game go to a new one in `LEVEL_DIR`.
+    pub fn save(&mut self) -> bool {
+        if !self.validate() {
+            return false;
+        }
+        let path = match self.path.as_deref().filter(|path| is_custom(path)) {
+            Some(path) => path.to_path_buf(),
+            None => {
+                let number = (1..).find(|n| !level_path(*n).exists()).unwrap();
+                self.level.name = format!("CUSTOM {}", number);
+                level_path(number)
+            }
+        };
+
+        let saved = fs::create_dir_all(LEVEL_DIR).and_then(|_| fs::write(&path, self.level.to_text()));
+        match saved {
+            Ok(()) => {
+                self.path = Some(path);
+                self.message = Some(("SAVED".to_string(), OK_COLOR));
+                true
+            }
+            Err(err) => {
+                eprintln!("Could not save level {}: {}", path.display(), err);
+                self.message = Some(("SAVE FAILED".to_string(), ERROR_COLOR));
+                false
+            }
+        }
+    }
+
+    pub fn draw(&self, layout: &Layout, g: &mut G2d) {
+        let width = self.level.width();
+        let height = self.level.height();
+
+        draw_rectangle(BORDER_COLOR, 0, 0, width, 1, layout, g);
+        draw_rectangle(BORDER_COLOR, 0, height - 1, width, 1, layout, g);
+        draw_rectangle(BORDER_COLOR, width - 1, 0, 1, height, layout, g);
+        draw_rectangle(BORDER_COLOR, 0, 0, 1, height, layout, g);
+        for (x, y) in self.level.walls() {
+            draw_block(BORDER_COLOR, x, y, layout, g);
+        }
+
+        for zone in &self.level.enemy_zones {
+            draw_rectangle(ENEMY_ZONE_COLOR, zone.x, zone.y, zone.width, zone.height, layout, g);
+        }
+        for zone in &self.level.no_food_zones {
+            draw_rectangle(NO_FOOD_ZONE_COLOR, zone.x, zone.y, zone.width, zone.height, layout, g);
+        }
+        if let Some((corner_x, corner_y)) = self.zone_corner {
+            let (x, y) = self.cursor;
+            let (left, top) = (corner_x.min(x), corner_y.min(y));
+            draw_rectangle(NEW_ZONE_COLOR, left, top, (corner_x - x).abs() + 1, (corner_y - y).abs() + 1, layout, g);
+        }
+
+        for (player, spawn) in self.level.spawns.iter().enumerate() {
+            draw_snake(&Snake::facing(spawn.x, spawn.y, spawn.direction), player, layout, g);
+        }
+        draw_block(CURSOR_COLOR, self.cursor.0, self.cursor.1, layout, g);
+
+        // Side panel
+        let panel_x = (width + 1) as f64;
+        let label_color: Color = [0.5, 0.5, 0.5, 1.0];
+        let value_color: Color = [0.0, 1.0, 1.0, 1.0];
+        draw_text([0.5, 0.0, 1.0, 1.0], "EDITOR", panel_x, 0.5, TEXT_NORMAL, layout, g);
+        draw_text(label_color, "MAP", panel_x, 2.0, TEXT_SMALL, layout, g);
+        draw_text(value_color, &self.level.name, panel_x, 3.0, TEXT_SMALL, layout, g);
+        draw_text(label_color, "TOOL", panel_x, 4.5, TEXT_SMALL, layout, g);
+        draw_text(value_color, &TOOLS[self.tool].get_name(), panel_x, 5.5, TEXT_SMALL, layout, g);
+        let position = format!("X {} Y {}", self.cursor.0, self.cursor.1);
+        draw_text(label_color, &position, panel_x, 7.0, TEXT_SMALL, layout, g);
+        if let Some((message, color)) = &self.message {
+            draw_text(*color, message, panel_x, 8.5, TEXT_SMALL, layout, g);
+        }
+
+        let help = ["TAB: TOOL", "SPACE: PLACE", "DEL: ERASE", "S: SAVE", "T: PLAY-TEST", "ESC: BACK"];
+        for (i, line) in help.iter().enumerate() {
+            draw_text(label_color, line, panel_x, 10.5 + i as f64, TEXT_SMALL, layout, g);
+        }
+    }
+}
+
+fn level_path(number: u32) -> PathBuf {
+    Path::new(LEVEL_DIR).join(format!("custom-{}.txt", number))
+}
+
+// Whether `path` is one of the editor's own `custom-<n>.txt` files
+fn is_custom(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .and_then(|name| name.strip_prefix("custom-"))
+        .and_then(|name| name.strip_suffix(".txt"))
+        .is_some_and(|number| number.parse::<u32>().is_ok())
+}
+
+fn turn_right(direction: Direction) -> Direction {
+    match direction {
+        Direction::Up => Direction::Right,
+        Direction::Right => Direction::Down,
+        Direction::Down => Direction::Left,
+        Direction::Left => Direction::Up,
+    }
+}
+
+/// Checks every start is clear with room to move, every start can reach
+/// player 1's, and there is room for food. Messages are short enough for
+/// the side panel.
+fn validate(level: &Level) -> Result<(), String> {
+    let is_open = |(x, y): (i32, i32)| level.is_inside(x, y) && !level.is_wall(x, y);
+
+    for (player, spawn) in level.spawns.iter().enumerate() {
+        if !spawn.cells().all(is_open) {
+            return Err(format!("P{} IN A WALL", player + 1));
+        }
+        let (dx, dy) = spawn.direction.offset();
+        if !is_open((spawn.x + dx, spawn.y + dy)) {
+            return Err(format!("P{} BLOCKED", player + 1));
+        }
+        for (other, other_spawn) in level.spawns.iter().enumerate().take(player) {
+            if spawn.cells().any(|cell| other_spawn.cells().any(|other_cell| other_cell == cell)) {
+                return Err(format!("P{} HITS P{}", player + 1, other + 1));
+            }
+        }
+    }
+    for zone in &level.enemy_zones {
+        let mut cells = (zone.y..zone.y + zone.height).flat_map(|y| (zone.x..zone.x + zone.width).map(move |x| (x, y)));
+        if !cells.any(is_open) {
+            return Err("EMPTY ZONE".to_string());
+        }
+    }
+
+    // Flood fill the open cells reachable from player 1's start
+    let width = level.width();
+    let mut reached = vec![false; (width * level.height()) as usize];
+    let start = (level.spawns[0].x, level.spawns[0].y);
+    reached[(start.1 * width + start.0) as usize] = true;
+    let mut queue = VecDeque::from([start]);
+    while let Some((x, y)) = queue.pop_front() {
+        for direction in [Direction::Up, Direction::Down, Direction::Left, Direction::Right] {
+            let (dx, dy) = direction.offset();
+            let next = (x + dx, y + dy);
+            let index = (next.1 * width + next.0) as usize;
+            if is_open(next) && !reached[index] {
+                reached[index] = true;
+                queue.push_back(next);
+            }
+        }
+    }
+    let is_reached = |(x, y): (i32, i32)| reached[(y * width + x) as usize];
+
+    for (player, spawn) in level.spawns.iter().enumerate().skip(1) {
+        if !is_reached((spawn.x, spawn.y)) {
+            return Err(format!("P{} CUT OFF", player + 1));
+        }
+    }
+
+    let spawn_cells: Vec<(i32, i32)> = level.spawns.iter().flat_map(|spawn| spawn.cells()).collect();
+    let food_cells = (1..level.height() - 1)
+        .flat_map(|y| (1..width - 1).map(move |x| (x, y)))
+        .filter(|&cell| is_reached(cell) && level.allows_food(cell.0, cell.1) && !spawn_cells.contains(&cell))
+        .count();
+    if food_cells < MIN_FOOD_CELLS {
+        return Err("NO FOOD ROOM".to_string());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn open_board_passes() {
+        assert_eq!(validate(&Level::open(30, 30)), Ok(()));
+    }
+
+    #[test]
+    fn rejects_blocked_start() {
+        let mut level = Level::open(30, 30);
+        let spawn = level.spawns[0];
+        let (dx, dy) = spawn.direction.offset();
+        level.set_wall(spawn.x + dx, spawn.y + dy, true);
+        assert_eq!(validate(&level), Err("P1 BLOCKED".to_string()));
+
+        let mut level = Level::open(30, 30);
+        level.set_wall(spawn.x, spawn.y, true);
+        assert_eq!(validate(&level), Err("P1 IN A WALL".to_string()));
+    }
+
+    #[test]
+    fn rejects_overlapping_starts() {
+        let mut level = Level::open(30, 30);
+        level.spawns[1] = level.spawns[0];
+        assert_eq!(validate(&level), Err("P2 HITS P1".to_string()));
+    }
+
+    #[test]
+    fn rejects_unreachable_start() {
+        // A wall down the board between player 1's start and player 2's
+        let mut level = Level::open(30, 30);
+        let x = (level.spawns[0].x + level.spawns[1].x) / 2;
+        for y in 1..level.height() - 1 {
+            level.set_wall(x, y, true);
+        }
+        assert_eq!(validate(&level), Err("P2 CUT OFF".to_string()));
+    }
+
+    #[test]
+    fn rejects_no_room_for_food() {
+        let mut level = Level::open(30, 30);
+        level.no_food_zones.push(Zone { x: 0, y: 0, width: 30, height: 30 });
+        assert_eq!(validate(&level), Err("NO FOOD ROOM".to_string()));
+    }
+
+    #[test]
+    fn overwrites_only_its_own_files() {
+        assert!(is_custom(&level_path(3)));
+        assert!(!is_custom(&Path::new(LEVEL_DIR).join("cross.txt")));
+        assert!(!is_custom(&Path::new(LEVEL_DIR).join("custom-map.txt")));
+    }
+}
@@ -1,109 +1,206 @@
-use piston_window::{Context, G2d, ellipse};
-use piston_window::types::Color;
+use crate::pathfind::Grid;
 use crate::snake::Direction;
-use crate::draw::{draw_circle, to_coord};
-use rand::{rng, Rng};
+use rand::Rng;
 
-const ENEMY_COLOR: Color = [0.8, 0.0, 0.0, 1.0]; // Red enemy
-const ENEMY_EYE_COLOR: Color = [1.0, 1.0, 1.0, 1.0]; // White eyes
+const DIRECTIONS: [Direction; 4] = [Direction::Up, Direction::Down, Direction::Left, Direction::Right];
+
+// Side of the square a patroller walks around, in cells
+const PATROL_SIZE: i32 = 6;
+
+#[derive(Copy, Clone, PartialEq)]
+pub enum EnemyKind {
+    Wanderer,   // Walks about at random
+    Patroller,  // Walks the same square over and over
+    WallHugger, // Follows walls and the border, keeping them on its right
+    Thief,      // Makes for the nearest food and steals it
+    Chaser,     // Cuts off the nearest snake, making for the cell ahead of its head
+}
+
+/// Kinds in the order they join the match.
+pub const ALL_ENEMY_KINDS: [EnemyKind; 5] = [
+    EnemyKind::Wanderer,
+    EnemyKind::Patroller,
+    EnemyKind::WallHugger,
+    EnemyKind::Thief,
+    EnemyKind::Chaser,
+];
+
+impl EnemyKind {
+    /// First level this kind turns up on.
+    pub fn first_level(&self) -> i32 {
+        match self {
+            EnemyKind::Wanderer => 1,
+            EnemyKind::Patroller => 2,
+            EnemyKind::WallHugger => 3,
+            EnemyKind::Thief => 4,
+            EnemyKind::Chaser => 5,
+        }
+    }
+}
+
+/// What an enemy can see of the match when it moves.
+pub struct Surroundings {
+    pub grid: Grid, // Everything in the way, enemies included
+    pub prey: Vec<(i32, i32)>, // Cells just ahead of living snakes' heads
+    pub foods: Vec<(i32, i32)>,
+}
+
+impl Surroundings {
+    // The cell one step from `cell`, or None if the border or a wall is there
+    fn step(&self, cell: (i32, i32), direction: Direction) -> Option<(i32, i32)> {
+        let (x, y) = self.grid.neighbour(cell, direction);
+        self.grid.is_open(x, y, 0).then_some((x, y))
+    }
+
+    // First step of a shortest path to the nearest of `goals`
+    fn first_step(&self, from: (i32, i32), goals: &[(i32, i32)]) -> Option<Direction> {
+        self.grid.first_step(from, |cell| goals.contains(&cell))
+    }
+
+    // First step of a shortest path to whichever prey looks closest
+    fn first_step_to_prey(&self, from: (i32, i32)) -> Option<Direction> {
+        let target = self.prey.iter().min_by_key(|&&cell| self.grid.distance(from, cell))?;
+        let path = self.grid.a_star(from, *target)?;
+        self.grid.direction_to(from, path[0])
+    }
+}
 
 pub struct Enemy {
+    kind: EnemyKind,
     x: i32,
     y: i32,
     direction: Direction,
     move_counter: i32,
     change_direction_counter: i32,
+    route: Vec<(i32, i32)>, // Patroller corners, walked in order
+    waypoint: usize,
 }
 
 impl Enemy {
-    pub fn new(x: i32, y: i32) -> Enemy {
+    pub fn new(x: i32, y: i32, kind: EnemyKind) -> Enemy {
+        // Patrollers walk a square with their starting cell as one corner
+        let route = match kind {
+            EnemyKind::Patroller => vec![(x, y), (x + PATROL_SIZE, y), (x + PATROL_SIZE, y + PATROL_SIZE), (x, y + PATROL_SIZE)],
+            _ => Vec::new(),
+        };
         Enemy {
+            kind,
             x,
             y,
             direction: Direction::Right,
             move_counter: 0,
             change_direction_counter: 0,
+            route,
+            waypoint: 0,
         }
     }
-    
+
+    pub fn kind(&self) -> EnemyKind {
+        self.kind
+    }
+
     pub fn position(&self) -> (i32, i32) {
         (self.x, self.y)
     }
-    
-    pub fn draw(&self, con: &Context, g: &mut G2d) {
-        // Draw enemy as a red circle with white eyes
-        draw_circle(ENEMY_COLOR, self.x, self.y, con, g);
-        
-        // Draw eyes
-        let gui_x = to_coord(self.x);
-        let gui_y = to_coord(self.y);
-        ellipse(
-            ENEMY_EYE_COLOR,
-            [gui_x + 6.0, gui_y + 6.0, 4.0, 4.0],
-            con.transform,
-            g,
-        );
-        ellipse(
-            ENEMY_EYE_COLOR,
-            [gui_x + 15.0, gui_y + 6.0, 4.0, 4.0],
-            con.transform,
-            g,
-        );
+
+    pub fn direction(&self) -> Direction {
+        self.direction
     }
-    
-    pub fn update(&mut self, width: i32, height: i32) {
-        self.change_direction_counter += 1;
-        
-        // Change direction randomly every 3-5 moves
-        if self.change_direction_counter >= 3 {
-            let mut rng = rng();
-            if rng.random_range(0..100) < 30 { // 30% chance to change direction
-                let directions = [Direction::Up, Direction::Down, Direction::Left, Direction::Right];
-                self.direction = directions[rng.random_range(0..directions.len())];
+
+    /// Moves the enemy the way its kind does, a cell at a time across
+    /// `surroundings.grid`. Any cell the grid blocks turns it back; on a
+    /// wrapping grid the border leads round to the far side instead.
+    pub fn update(&mut self, surroundings: &Surroundings, rng: &mut impl Rng) {
+        // Wanderers change direction randomly every 3-5 moves
+        if self.kind == EnemyKind::Wanderer {
+            self.change_direction_counter += 1;
+            if self.change_direction_counter >= 3 {
+                if rng.random_range(0..100) < 30 { // 30% chance to change direction
+                    self.direction = DIRECTIONS[rng.random_range(0..DIRECTIONS.len())];
+                }
+                self.change_direction_counter = 0;
             }
-            self.change_direction_counter = 0;
         }
-        
+
         // Move enemy
         self.move_counter += 1;
-        if self.move_counter >= 2 { // Enemy moves slower than snake
-            self.move_counter = 0;
-            
-            match self.direction {
-                Direction::Up => {
-                    if self.y > 1 {
-                        self.y -= 1;
-                    } else {
-                        self.direction = Direction::Down;
-                    }
-                }
-                Direction::Down => {
-                    if self.y < height - 2 {
-                        self.y += 1;
-                    } else {
-                        self.direction = Direction::Up;
-                    }
-                }
-                Direction::Left => {
-                    if self.x > 1 {
-                        self.x -= 1;
-                    } else {
-                        self.direction = Direction::Right;
-                    }
-                }
-                Direction::Right => {
-                    if self.x < width - 2 {
-                        self.x += 1;
-                    } else {
-                        self.direction = Direction::Left;
-                    }
+        if self.move_counter < 2 { // Enemy moves slower than snake
+            return;
+        }
+        self.move_counter = 0;
+
+        let direction = match self.kind {
+            EnemyKind::Wanderer => Some(self.direction),
+            EnemyKind::Patroller => self.patrol_direction(surroundings),
+            EnemyKind::WallHugger => self.hug_direction(surroundings),
+            EnemyKind::Thief => surroundings.first_step(self.position(), &surroundings.foods),
+            EnemyKind::Chaser => surroundings.first_step_to_prey(self.position()),
+        };
+        let Some(direction) = direction else {
+            return; // Nothing to go for, or no way there
+        };
+        self.direction = direction;
+
+        match surroundings.step(self.position(), direction) {
+            Some((x, y)) => (self.x, self.y) = (x, y),
+            None => {
+                // Turn back at the border and at map walls
+                self.direction = direction.opposite();
+                if self.kind == EnemyKind::Patroller {
+                    // Walk the route the other way round, back to the
+                    // corner it came from
+                    self.route.reverse();
+                    self.waypoint = (self.route.len() - self.waypoint) % self.route.len();
                 }
             }
         }
     }
-    
+
+    // Straight at the next corner of the route, across first. Corners off
+    // the board or in a wall are skipped.
+    fn patrol_direction(&mut self, surroundings: &Surroundings) -> Option<Direction> {
+        for _ in 0..self.route.len() {
+            let (x, y) = self.route[self.waypoint];
+            if (x, y) != (self.x, self.y) && surroundings.grid.is_open(x, y, 0) {
+                break;
+            }
+            self.waypoint = (self.waypoint + 1) % self.route.len();
+        }
+        let (target_x, target_y) = self.route[self.waypoint];
+        let direction = if target_x > self.x {
+            Direction::Right
+        } else if target_x < self.x {
+            Direction::Left
+        } else if target_y > self.y {
+            Direction::Down
+        } else {
+            Direction::Up
+        };
+        Some(direction)
+    }
+
+    // Right-hand rule: turn right if it can, else straight on, else left,
+    // else back
+    fn hug_direction(&self, surroundings: &Surroundings) -> Option<Direction> {
+        let right = match self.direction {
+            Direction::Up => Direction::Right,
+            Direction::Right => Direction::Down,
+            Direction::Down => Direction::Left,
+            Direction::Left => Direction::Up,
+        };
+        // Out in the open, with nothing to its right or just behind it,
+        // it keeps going until it finds something to follow
+        let behind = surroundings.grid.neighbour(self.position(), self.direction.opposite());
+        let beside_wall = surroundings.step(self.position(), right).is_none() || surroundings.step(behind, right).is_none();
+        let choices = if beside_wall {
+            [right, self.direction, right.opposite(), self.direction.opposite()]
+        } else {
+            [self.direction, right.opposite(), right, self.direction.opposite()]
+        };
+        choices.into_iter().find(|&direction| surroundings.step(self.position(), direction).is_some())
+    }
+
     pub fn check_collision(&self, x: i32, y: i32) -> bool {
         self.x == x && self.y == y
     }
 }
-
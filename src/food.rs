@@ -0,0 +1,124 @@
+//! Things to eat. Every kind has its own score, effect on the snake that
+//! eats it, chance of turning up and time on the board.
+
+use crate::sim::TICK_RATE;
+
+#[derive(Copy, Clone, PartialEq)]
+pub enum FoodKind {
+    Apple,
+    Golden, // Worth more, but doesn't stay long
+    Berry,  // Shrinks the snake
+    Speed,  // Speeds the snake up for a while
+    Slow,   // Slows the snake down for a while
+}
+
+pub const ALL_FOOD_KINDS: [FoodKind; 5] = [
+    FoodKind::Apple,
+    FoodKind::Golden,
+    FoodKind::Berry,
+    FoodKind::Speed,
+    FoodKind::Slow,
+];
+
+/// Seconds a speed or slow fruit keeps working.
+pub const TEMPO_SECONDS: u32 = 5;
+
+// Timed foods blink for their last two seconds
+const BLINK_TICKS: u32 = 2 * TICK_RATE;
+
+impl FoodKind {
+    pub fn points(&self) -> i32 {
+        match self {
+            FoodKind::Apple => 10,
+            FoodKind::Golden => 50,
+            FoodKind::Berry => 5,
+            FoodKind::Speed | FoodKind::Slow => 15,
+        }
+    }
+
+    /// Blocks the snake gains (or loses, if negative) by eating it.
+    pub fn growth(&self) -> i32 {
+        match self {
+            FoodKind::Berry => -2,
+            _ => 1,
+        }
+    }
+
+    /// How much longer the eater takes per move for `TEMPO_SECONDS`, if
+    /// this is a speed or slow fruit.
+    pub fn tempo(&self) -> Option<f64> {
+        match self {
+            FoodKind::Speed => Some(0.6),
+            FoodKind::Slow => Some(1.6),
+            _ => None,
+        }
+    }
+
+    /// Relative chance of this kind when an extra food appears.
+    pub fn weight(&self) -> u32 {
+        match self {
+            FoodKind::Apple => 40,
+            FoodKind::Golden => 10,
+            FoodKind::Berry => 20,
+            FoodKind::Speed | FoodKind::Slow => 15,
+        }
+    }
+
+    /// Ticks before it disappears uneaten. Apples stay until eaten.
+    pub fn lifetime(&self) -> Option<u32> {
+        match self {
+            FoodKind::Apple => None,
+            FoodKind::Golden => Some(6 * TICK_RATE),
+            FoodKind::Berry | FoodKind::Speed | FoodKind::Slow => Some(10 * TICK_RATE),
+        }
+    }
+
+    /// Picks a kind by weight from a roll in `0..total_weight()`.
+    pub fn from_roll(mut roll: u32) -> FoodKind {
+        for kind in ALL_FOOD_KINDS {
+            if roll < kind.weight() {
+                return kind;
+            }
+            roll -= kind.weight();
+        }
+        FoodKind::Apple
+    }
+
+    pub fn total_weight() -> u32 {
+        ALL_FOOD_KINDS.iter().map(|kind| kind.weight()).sum()
+    }
+}
+
+#[derive(Copy, Clone)]
+pub struct Food {
+    pub x: i32,
+    pub y: i32,
+    pub kind: FoodKind,
+    pub ticks_left: Option<u32>, // Until it disappears, for kinds that do
+}
+
+impl Food {
+    pub fn new(x: i32, y: i32, kind: FoodKind) -> Food {
+        Food { x, y, kind, ticks_left: kind.lifetime() }
+    }
+
+    pub fn position(&self) -> (i32, i32) {
+        (self.x, self.y)
+    }
+
+    /// Whether a food about to disappear is in the off half of its blink.
+    pub fn blinked_out(&self) -> bool {
+        self.ticks_left.is_some_and(|ticks| ticks < BLINK_TICKS && (ticks / 8) % 2 == 1)
+    }
+
+    /// Counts down the food's lifetime. Returns false once it has run out.
+    pub fn tick(&mut self) -> bool {
+        match &mut self.ticks_left {
+            Some(ticks) => {
+                *ticks = ticks.saturating_sub(1);
+                *ticks > 0
+            }
+            None => true,
+        }
+    }
+}
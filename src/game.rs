@@ -1,612 +1,459 @@
 use piston_window::*;
 use piston_window::types::Color;
 
-use rand::{rng, Rng};
+use crate::snake::Direction;
+use crate::controls::{Action, Controls};
+use crate::draw::{
+    draw_block, draw_rectangle, draw_enemy, draw_food, draw_power_up, draw_power_up_icon, draw_snake, draw_text,
+    draw_text_centered, Layout, TEXT_LARGE, TEXT_NORMAL,
+};
+use crate::mode::PlayerMode;
+use crate::powerup::{PowerUpKind, ALL_POWER_UPS};
+use crate::sim::{MatchConfig, MatchResult, Simulation, TickEvents, TickInputs, MAX_PLAYERS, TICK_SECONDS};
+use crate::level::Level;
+use crate::replay::Replay;
+use crate::controller::{next_turn, SnakeController};
 
-use crate::snake::{Direction, Snake};
-use crate::draw::{draw_rectangle, draw_apple, draw_block};
-use crate::menu::GameMode;
-use crate::enemy::Enemy;
+/// Columns right of the board taken by the side panel.
+pub const PANEL_COLUMNS: i32 = 12;
 
-const BORDER_COLOR: Color = [0.2, 0.3, 0.4, 1.0];
+pub const BORDER_COLOR: Color = [0.2, 0.3, 0.4, 1.0];
+const PORTAL_COLORS: [Color; 2] = [[0.5, 0.2, 0.8, 1.0], [0.2, 0.6, 0.9, 1.0]]; // Alternating purple and blue
 const GAMEOVER_COLOR: Color = [0.9, 0.1, 0.0, 0.5];
+const SCORE_COLORS: [Color; MAX_PLAYERS] = [
+    [1.0, 1.0, 0.0, 1.0], // Yellow
+    [1.0, 0.5, 0.0, 1.0], // Orange
+    [1.0, 0.4, 0.7, 1.0], // Pink
+    [0.8, 0.8, 0.8, 1.0], // White
+];
+const TEAM_COLOR: Color = [0.4, 1.0, 0.4, 1.0];
+const DEAD_COLOR: Color = [0.4, 0.4, 0.4, 1.0];
+const CORPSE_COLOR: Color = [0.35, 0.35, 0.35, 1.0];
+const LEVEL_COLOR: Color = [0.0, 0.5, 1.0, 1.0];
+const MODE_COLOR: Color = [0.5, 0.0, 1.0, 1.0]; // Purple
+const HIGH_SCORE_COLOR: Color = [1.0, 0.8, 0.0, 1.0];
+const GOAL_COLOR: Color = [0.0, 1.0, 0.5, 1.0];
+
+// Panel row of player 1's power-ups, below the campaign goal
+const POWER_UP_ROW: f64 = 17.3;
 
 const RESTART_TIME: f64 = 3.0; // Give player time to see game over screen
-const FOOD_PER_LEVEL: i32 = 5; // Number of foods needed to level up
+const MAX_FRAME_TIME: f64 = 0.25; // Don't try to catch up on longer stalls
+
+// Feeds a recorded match back into the simulation
+struct Playback {
+    inputs: Vec<TickInputs>,
+    cursor: usize,
+    paused: bool,
+    speed: f64,
+    step_requested: bool,
+}
+
+impl Playback {
+    fn next_inputs(&mut self) -> TickInputs {
+        let inputs = self.inputs.get(self.cursor).copied().unwrap_or_default();
+        self.cursor += 1;
+        inputs
+    }
+}
 
+/// Piston frontend for a `Simulation`: turns key presses into inputs and
+/// renders the board, HUD and game-over screen. Live matches are recorded
+/// and saved as a replay at game over.
 pub struct Game {
-    snake1: Snake,  // Player 1 (Arrow keys)
-    snake2: Snake,  // Player 2 (WASD keys)
+    sim: Simulation,
+    waiting_time: f64,
 
-    food_exist: bool,
-    food_x: i32,
-    food_y: i32,
+    // Real time not yet consumed by whole ticks
+    tick_accumulator: f64,
 
-    width: i32,
-    height: i32,
+    // Direction changes pressed since the last tick, per player
+    pending: [Option<Direction>; MAX_PLAYERS],
 
-    game_over: bool,
-    waiting_time: f64,
-    
-    score1: i32,  // Player 1 score
-    score2: i32,  // Player 2 score
-    level: i32,
-    foods_eaten: i32,
-    
-    game_mode: GameMode,
-    final_score: i32,
-    final_level: i32,
-    
-    // Timer mode
-    game_time: f64,
-    time_limit: f64,
-    
-    // High score tracking
-    high_score: i32,
-    
-    // Enemies (appears from level 3, more as levels increase)
-    enemies: Vec<Enemy>,
-    enemy_move_time: f64,
-    
-    // Score multiplier power-up (for player 1 in Hard mode)
-    score_multiplier: i32,
-    multiplier_timer: f64,
-    multiplier_duration: f64,
+    replay: Replay,
+    playback: Option<Playback>,
+
+    // Best score on the leaderboard for this mode when the match started
+    best_score: i32,
+
+    controls: Controls,
+
+    // Players steered by a controller instead of the keyboard
+    controllers: [Option<Box<dyn SnakeController>>; MAX_PLAYERS],
 }
 
 impl Game {
-    pub fn new(width: i32, height: i32, game_mode: GameMode) -> Game {
-        let time_limit = game_mode.get_time_limit();
-        let center_x = width / 2;
-        let center_y = height / 2;
-        // Create snakes facing opposite directions with more spacing
-        // Player 1: left side, facing right (away from center)
-        let snake1 = Snake::new(center_x - 8, center_y);
-        // Player 2: right side, facing left (away from center)
-        let snake2 = Snake::new_left(center_x + 8, center_y);
+    pub fn new(config: MatchConfig, level: Level) -> Game {
+        let sim = Simulation::new(config, level);
+        let replay = Replay::new(*sim.config(), sim.map().clone());
         Game {
-            snake1,
-            snake2,
+            sim,
             waiting_time: 0.0,
-            food_exist: false,
-            food_x: 0,
-            food_y: 0,
-            width,
-            height,
-            game_over: false,
-            score1: 0,
-            score2: 0,
-            level: 1,
-            foods_eaten: 0,
-            game_mode,
-            final_score: 0,
-            final_level: 1,
-            game_time: 0.0,
-            time_limit,
-            high_score: 0,
-            enemies: Vec::new(),
-            enemy_move_time: 0.0,
-            score_multiplier: 1,
-            multiplier_timer: 0.0,
-            multiplier_duration: 0.0,
+            tick_accumulator: 0.0,
+            pending: [None; MAX_PLAYERS],
+            replay,
+            playback: None,
+            best_score: 0,
+            controls: Controls::default(),
+            controllers: Default::default(),
+        }
+    }
+
+    /// Plays a recorded match back through the same simulation.
+    pub fn from_replay(replay: Replay) -> Game {
+        let mut game = Game::new(replay.config, replay.level);
+        game.playback = Some(Playback {
+            inputs: replay.inputs,
+            cursor: 0,
+            paused: false,
+            speed: 1.0,
+            step_requested: false,
+        });
+        game
+    }
+
+    pub fn set_best_score(&mut self, best_score: i32) {
+        self.best_score = best_score;
+    }
+
+    pub fn set_controls(&mut self, controls: Controls) {
+        self.controls = controls;
+    }
+
+    /// Hands `player`'s snake to `controller`, or back to the keyboard.
+    pub fn set_controller(&mut self, player: usize, controller: Option<Box<dyn SnakeController>>) {
+        self.controllers[player] = controller;
+    }
+
+    pub fn sim(&self) -> &Simulation {
+        &self.sim
+    }
+
+    /// Who can claim a leaderboard entry: each human player on their own,
+    /// or the whole team in co-op if anyone on it is human. Returns (label,
+    /// colour, score).
+    pub fn results(&self) -> Vec<(String, Color, i32)> {
+        let humans = (0..self.sim.snakes().len()).filter(|&player| self.controllers[player].is_none());
+        if self.sim.player_mode().shares_score() {
+            if humans.count() == 0 {
+                return Vec::new();
+            }
+            return vec![("TEAM".to_string(), TEAM_COLOR, self.sim.team_score())];
+        }
+        self.sim.scores()
+            .iter()
+            .enumerate()
+            .filter(|(player, _)| self.controllers[*player].is_none())
+            .map(|(player, score)| (format!("PLAYER {}", player + 1), SCORE_COLORS[player], *score))
+            .collect()
+    }
+
+    /// The score this match is judged by: the team's in co-op, otherwise
+    /// the best single player's.
+    pub fn result_score(&self) -> i32 {
+        if self.sim.player_mode().shares_score() {
+            self.sim.team_score()
+        } else {
+            self.sim.scores().iter().copied().max().unwrap_or(0)
         }
     }
-    
+
+    pub fn is_playback(&self) -> bool {
+        self.playback.is_some()
+    }
+
     pub fn restart_game(&mut self) {
-        let center_x = self.width / 2;
-        let center_y = self.height / 2;
-        self.snake1 = Snake::new(center_x - 8, center_y);
-        self.snake2 = Snake::new_left(center_x + 8, center_y);
+        self.sim.restart();
         self.waiting_time = 0.0;
-        self.food_exist = false;
-        self.food_x = 0;
-        self.food_y = 0;
-        self.game_over = false;
-        self.score1 = 0;
-        self.score2 = 0;
-        self.level = 1;
-        self.foods_eaten = 0;
-        self.final_score = 0;
-        self.final_level = 1;
-        self.enemies.clear();
-        self.enemy_move_time = 0.0;
-        self.score_multiplier = 1;
-        self.multiplier_timer = 0.0;
-        self.multiplier_duration = 0.0;
+        self.tick_accumulator = 0.0;
+        self.pending = [None; MAX_PLAYERS];
+        self.replay.inputs.clear();
+        if let Some(playback) = &mut self.playback {
+            playback.cursor = 0;
+        }
     }
 
     pub fn key_pressed(&mut self, key: Key) {
-        if self.game_over {
+        if self.sim.is_game_over() {
             return;
         }
 
-        // Player 1 controls (Arrow keys)
-        let dir1 = match key {
-            Key::Up => Some(Direction::Up),
-            Key::Down => Some(Direction::Down),
-            Key::Left => Some(Direction::Left),
-            Key::Right => Some(Direction::Right),
-            _ => None,
-        };
-        
-        if let Some(dir) = dir1 {
-            if dir != self.snake1.head_direction().opposite() {
-                self.update_snake1(Some(dir));
+        // Playback controls replace the player controls
+        if let Some(playback) = &mut self.playback {
+            match key {
+                Key::Space => playback.paused = !playback.paused,
+                Key::Right | Key::Period => {
+                    playback.paused = true;
+                    playback.step_requested = true;
+                }
+                Key::D1 => playback.speed = 1.0,
+                Key::D2 => playback.speed = 2.0,
+                Key::D4 => playback.speed = 4.0,
+                _ => {}
             }
             return;
         }
 
-        // Player 2 controls (WASD keys)
-        let dir2 = match key {
-            Key::W => Some(Direction::Up),
-            Key::S => Some(Direction::Down),
-            Key::A => Some(Direction::Left),
-            Key::D => Some(Direction::Right),
-            _ => None,
-        };
-        
-        if let Some(dir) = dir2 {
-            if dir != self.snake2.head_direction().opposite() {
-                self.update_snake2(Some(dir));
+        if let Some((player, action)) = self.controls.action(key) {
+            self.player_action(player, action);
+        }
+    }
+
+    /// An action from a player's keyboard keys or gamepad.
+    pub fn player_action(&mut self, player: usize, action: Action) {
+        if self.sim.is_game_over() || self.playback.is_some() {
+            return;
+        }
+
+        // Player 2's controls also steer a solo snake. Bots' snakes ignore
+        // the keys for their slot.
+        if let Some(dir) = action.direction() {
+            let player = player.min(self.sim.snakes().len() - 1);
+            if self.controllers[player].is_none() {
+                self.pending[player] = Some(dir);
             }
         }
     }
 
-    pub fn draw(&self, con: &Context, g: &mut G2d) {
-        // Draw both snakes with different colors
-        self.snake1.draw(con, g, true);  // Player 1 - Green
-        self.snake2.draw(con, g, false); // Player 2 - Blue
+    pub fn draw(&self, layout: &Layout, g: &mut G2d) {
+        let width = self.sim.width();
+        let height = self.sim.height();
 
-        if self.food_exist {
-            draw_apple(self.food_x, self.food_y, con, g);
+        // Map walls
+        for (x, y) in self.sim.map().walls() {
+            draw_block(BORDER_COLOR, x, y, layout, g);
         }
-        
-        // Draw enemies (level 3+)
-        for enemy in &self.enemies {
-            enemy.draw(con, g);
+
+        // Draw every living snake in its player's colour, and what is left of the dead ones
+        for (player, snake) in self.sim.snakes().iter().enumerate() {
+            if self.sim.is_alive(player) {
+                draw_snake(snake, player, layout, g);
+            }
+        }
+        for &(x, y) in self.sim.obstacles() {
+            draw_block(CORPSE_COLOR, x, y, layout, g);
+        }
+
+        for food in self.sim.foods() {
+            draw_food(food, layout, g);
+        }
+        for item in self.sim.power_up_items() {
+            draw_power_up(item, layout, g);
         }
         
-        // Draw multiplier indicator if active (only in Hard mode)
-        if matches!(self.game_mode, crate::menu::GameMode::Hard) && self.score_multiplier > 1 {
-            let multiplier_color: Color = [1.0, 0.0, 1.0, 1.0]; // Magenta
-            let multiplier_y = 4;
-            // Draw "X2" or "X3" etc indicator
-            for i in 0..self.score_multiplier.min(5) {
-                draw_block(multiplier_color, self.width + 1 + i, multiplier_y, con, g);
+        // Draw enemies
+        for enemy in self.sim.enemies() {
+            draw_enemy(enemy, layout, g);
+        }
+
+        if self.sim.config().wrap_walls {
+            // Wrapping edges are portals: a striped border instead of a solid wall
+            for x in 0..width {
+                for y in [0, height - 1] {
+                    draw_block(PORTAL_COLORS[(x % 2) as usize], x, y, layout, g);
+                }
             }
-            // Show remaining time as blocks
-            let time_left = ((self.multiplier_duration - self.multiplier_timer) / 3.0) as i32;
-            let time_blocks = time_left.min(10);
-            for i in 0..time_blocks {
-                draw_block([0.5, 0.0, 0.5, 1.0], self.width + 1 + i, multiplier_y + 1, con, g);
+            for y in 1..height - 1 {
+                for x in [0, width - 1] {
+                    draw_block(PORTAL_COLORS[(y % 2) as usize], x, y, layout, g);
+                }
             }
+        } else {
+            draw_rectangle(BORDER_COLOR, 0, 0, width, 1, layout, g);
+            draw_rectangle(BORDER_COLOR, 0, height - 1, width, 1, layout, g);
+            draw_rectangle(BORDER_COLOR, width - 1, 0, 1, height, layout, g);
+            draw_rectangle(BORDER_COLOR, 0, 0, 1, height, layout, g);
         }
 
-        draw_rectangle(BORDER_COLOR, 0, 0, self.width, 1, con, g);
-        draw_rectangle(BORDER_COLOR, 0, self.height - 1, self.width, 1, con, g);
-        draw_rectangle(BORDER_COLOR, self.width - 1, 0, 1, self.height, con, g);
-        draw_rectangle(BORDER_COLOR, 0, 0, 1, self.height, con, g);
+        // Side panel, one line of text per row
+        let panel_x = (width + 1) as f64;
 
-        // Display Player 1 score (Yellow blocks)
-        let score1_color: Color = [1.0, 1.0, 0.0, 1.0];
-        let score1_blocks = (self.score1 / 10).min(10);
-        for i in 0..score1_blocks {
-            draw_block(score1_color, self.width + 1 + i, 0, con, g);
-        }
-        
-        // Display Player 2 score (Orange blocks)
-        let score2_color: Color = [1.0, 0.5, 0.0, 1.0];
-        let score2_blocks = (self.score2 / 10).min(10);
-        for i in 0..score2_blocks {
-            draw_block(score2_color, self.width + 1 + i, 1, con, g);
-        }
+        // Current game mode
+        draw_text(MODE_COLOR, self.sim.game_mode().get_name(), panel_x, 0.5, TEXT_NORMAL, layout, g);
 
-        // Display level visually using colored blocks
-        let level_color: Color = [0.0, 0.5, 1.0, 1.0];
-        let level_blocks = self.level.min(10); // Show up to 10 blocks
-        for i in 0..level_blocks {
-            draw_block(level_color, self.width + 1 + i, 2, con, g);
+        // Player scores (greyed out once dead), plus the pooled score in co-op
+        let mut row_y = 2.0;
+        for (player, score) in self.sim.scores().iter().enumerate() {
+            let color = if self.sim.is_alive(player) { SCORE_COLORS[player] } else { DEAD_COLOR };
+            draw_text(color, &format!("P{} {}", player + 1, score), panel_x, row_y, TEXT_NORMAL, layout, g);
+            row_y += 1.5;
         }
-        
-        // Display current game mode indicator
-        let mode_color: Color = [0.5, 0.0, 1.0, 1.0]; // Purple
-        let mode_indicator = match self.game_mode {
-            crate::menu::GameMode::Easy => 1,
-            crate::menu::GameMode::Medium => 2,
-            crate::menu::GameMode::Hard => 3,
-            crate::menu::GameMode::Timer => 4,
-            crate::menu::GameMode::Survival => 5,
-        };
-        for i in 0..mode_indicator.min(10) {
-            draw_block(mode_color, self.width + 1 + i, 3, con, g);
+        if self.sim.player_mode().shares_score() {
+            draw_text(TEAM_COLOR, &format!("TEAM {}", self.sim.team_score()), panel_x, row_y, TEXT_NORMAL, layout, g);
         }
-        
+
+        // Level
+        draw_text(LEVEL_COLOR, &format!("LEVEL {}", self.sim.get_level()), panel_x, 6.5, TEXT_NORMAL, layout, g);
+
         // Display timer for timer mode
-        if self.game_mode.is_timed_mode() {
-            let remaining_time = (self.time_limit - self.game_time).max(0.0) as i32;
-            let timer_blocks = (remaining_time / 5).min(10);
+        if self.sim.game_mode().is_timed_mode() {
+            let remaining_time = self.sim.time_remaining().ceil() as i32;
             let timer_color: Color = if remaining_time < 10 {
                 [1.0, 0.3, 0.0, 1.0] // Red when low
             } else {
                 [0.0, 1.0, 1.0, 1.0] // Cyan
             };
-            for i in 0..timer_blocks {
-                draw_block(timer_color, self.width + 1 + i, 3, con, g);
-            }
-        }
-
-        if self.game_over {
-            self.draw_game_over(con, g);
+            draw_text(timer_color, &format!("TIME {}", remaining_time), panel_x, 8.0, TEXT_NORMAL, layout, g);
         }
-    }
 
-    pub fn update(&mut self, delta_time: f64) {
-        self.waiting_time += delta_time;
+        // Best score on the leaderboard for this mode
+        let high_score = self.best_score.max(self.result_score());
+        draw_text(HIGH_SCORE_COLOR, &format!("BEST {}", high_score), panel_x, 11.0, TEXT_NORMAL, layout, g);
 
-        if self.game_over {
-            // Game over screen is handled in draw, no auto-restart
-            return;
-        }
-        
-        // Update game time for timer mode
-        if self.game_mode.is_timed_mode() {
-            self.game_time += delta_time;
-            if self.game_time >= self.time_limit {
-                self.game_over = true;
-                self.final_score = self.score1.max(self.score2);
-                self.final_level = self.level;
-                let max_score = self.score1.max(self.score2);
-                if max_score > self.high_score {
-                    self.high_score = max_score;
-                }
-                return;
-            }
+        // Playback state
+        if let Some(playback) = &self.playback {
+            let playback_color: Color = [0.0, 1.0, 0.5, 1.0];
+            let text = if playback.paused {
+                "PAUSED".to_string()
+            } else {
+                format!("REPLAY {}X", playback.speed as i32)
+            };
+            draw_text(playback_color, &text, panel_x, 13.0, TEXT_NORMAL, layout, g);
         }
 
-        if !self.food_exist {
-            self.add_food();
-        }
-        
-        // Update score multiplier timer (only in Hard mode)
-        if matches!(self.game_mode, crate::menu::GameMode::Hard) && self.score_multiplier > 1 {
-            self.multiplier_timer += delta_time;
-            if self.multiplier_timer >= self.multiplier_duration {
-                self.score_multiplier = 1;
-                self.multiplier_timer = 0.0;
+        // Running power-ups, one row per player: an icon each with a bar
+        // for the time left, the multiplier's showing its factor
+        for (player, &color) in SCORE_COLORS.iter().enumerate().take(self.sim.snakes().len()) {
+            let active = self.sim.power_ups(player);
+            let running: Vec<PowerUpKind> = ALL_POWER_UPS.into_iter().filter(|&kind| active.is_active(kind)).collect();
+            if running.is_empty() {
+                continue;
             }
-        } else if !matches!(self.game_mode, crate::menu::GameMode::Hard) {
-            // Reset multiplier if not in Hard mode
-            self.score_multiplier = 1;
-            self.multiplier_timer = 0.0;
-        }
-        
-        // Spawn enemies based on level - starting from level 1
-        // Level 1: 1 enemy, Level 3: 2 enemies, Level 5: 3 enemies, Level 7+: 4 enemies
-        let target_enemy_count = match self.level {
-            1..=2 => 1,
-            3..=4 => 2,
-            5..=6 => 3,
-            _ => 4,
-        };
-        
-        while self.enemies.len() < target_enemy_count {
-            self.spawn_enemy();
-        }
-        
-        // Update enemy movement
-        self.enemy_move_time += delta_time;
-        if self.enemy_move_time > 0.3 { // Enemies move every 0.3 seconds
-            for enemy in &mut self.enemies {
-                enemy.update(self.width, self.height);
+            let row_y = POWER_UP_ROW + player as f64 * 1.4;
+            draw_text(color, &format!("P{}", player + 1), panel_x, row_y, TEXT_NORMAL, layout, g);
+            for (i, kind) in running.into_iter().enumerate() {
+                let label = match kind {
+                    PowerUpKind::Multiplier => active.score_multiplier().to_string(),
+                    _ => kind.letter().to_string(),
+                };
+                let icon_x = panel_x + 2.0 + i as f64 * 1.3;
+                draw_power_up_icon(kind, &label, icon_x, row_y, Some(active.fraction_left(kind)), layout, g);
             }
-            self.enemy_move_time = 0.0;
         }
 
-        // Calculate moving period based on level and game mode
-        let base_speed = self.game_mode.get_base_speed();
-        let speed_multiplier = self.game_mode.get_speed_multiplier();
-        let moving_period = base_speed / (1.0 + (self.level as f64) * speed_multiplier);
-        if self.waiting_time > moving_period {
-            self.update_snake1(None);
-            self.update_snake2(None);
-            self.waiting_time = 0.0; // Reset timer after both snakes move
+        // Campaign goal and how close the players are to it
+        if let Some((goal, progress)) = self.sim.goal_progress() {
+            draw_text(GOAL_COLOR, goal.short_name(), panel_x, 14.5, TEXT_NORMAL, layout, g);
+            let text = format!("{}/{}", progress.min(goal.target()), goal.target());
+            draw_text(GOAL_COLOR, &text, panel_x, 16.0, TEXT_NORMAL, layout, g);
         }
-    }
 
-    fn check_eating(&mut self) {
-        let (head1_x, head1_y) = self.snake1.head_position();
-        let (head2_x, head2_y) = self.snake2.head_position();
-        
-        // Check if player 1 eats food
-        if self.food_exist && self.food_x == head1_x && self.food_y == head1_y {
-            self.food_exist = false;
-            self.snake1.restore_tail();
-            
-            let base_score = 10;
-            let final_score = if matches!(self.game_mode, crate::menu::GameMode::Hard) {
-                base_score * self.score_multiplier
-            } else {
-                base_score
-            };
-            self.score1 += final_score;
-            
-            // Random chance to spawn multiplier power-up (10% chance, only in Hard mode)
-            if matches!(self.game_mode, crate::menu::GameMode::Hard) {
-                let mut rng = rng();
-                if rng.random_range(0..100) < 10 && self.score_multiplier == 1 {
-                    self.score_multiplier = 2;
-                    self.multiplier_duration = 15.0;
-                    self.multiplier_timer = 0.0;
-                }
-            }
-            
-            self.foods_eaten += 1;
-            self.update_level();
-            return;
-        }
-        
-        // Check if player 2 eats food
-        if self.food_exist && self.food_x == head2_x && self.food_y == head2_y {
-            self.food_exist = false;
-            self.snake2.restore_tail();
-            self.score2 += 10; // Player 2 always gets base score
-            self.foods_eaten += 1;
-            self.update_level();
-        }
-    }
-    
-    fn update_level(&mut self) {
-        let new_level = (self.foods_eaten / FOOD_PER_LEVEL) + 1;
-        if new_level > self.level {
-            self.level = new_level;
+        if self.sim.is_game_over() {
+            self.draw_game_over(layout, g);
         }
     }
 
-    fn check_if_snake_alive(&self, snake: &Snake, other_snake: &Snake, dir: Option<Direction>) -> bool {
-        let (next_x, next_y): (i32, i32) = snake.next_head(dir);
-
-        // Check collision with own tail
-        if snake.overlap_tail(next_x, next_y) {
-            return false;
-        }
-        
-        // Check collision with other snake
-        if other_snake.overlap_tail(next_x, next_y) {
-            return false;
-        }
-        
-        // Check collision with enemies
-        for enemy in &self.enemies {
-            if enemy.check_collision(next_x, next_y) {
-                return false;
-            }
+    pub fn update(&mut self, delta_time: f64) {
+        if self.sim.is_game_over() {
+            // Game over screen is handled in draw, no auto-restart
+            self.waiting_time += delta_time;
+            return;
         }
-        
-        // Check wall collision
-        next_x > 0 && next_y > 0 && next_x < self.width - 1 && next_y < self.height - 1
-    }
-    
-    fn spawn_enemy(&mut self) {
-        use rand::{rng, Rng};
-        let mut rng = rng();
-        // Spawn enemy away from both snakes and food
-        let (snake1_x, snake1_y) = self.snake1.head_position();
-        let (snake2_x, snake2_y) = self.snake2.head_position();
-        let mut enemy_x = rng.random_range(1..self.width - 1);
-        let mut enemy_y = rng.random_range(1..self.height - 1);
-        
-        // Make sure enemy doesn't spawn on snakes, food, or other enemies
-        let mut attempts = 0;
-        loop {
-            let mut valid_position = true;
-            
-            // Check snake1 collision
-            if (enemy_x == snake1_x && enemy_y == snake1_y) || 
-               self.snake1.overlap_tail(enemy_x, enemy_y) {
-                valid_position = false;
-            }
-            
-            // Check snake2 collision
-            if (enemy_x == snake2_x && enemy_y == snake2_y) || 
-               self.snake2.overlap_tail(enemy_x, enemy_y) {
-                valid_position = false;
-            }
-            
-            // Check food collision
-            if enemy_x == self.food_x && enemy_y == self.food_y {
-                valid_position = false;
-            }
-            
-            // Check other enemies
-            for existing_enemy in &self.enemies {
-                if existing_enemy.check_collision(enemy_x, enemy_y) {
-                    valid_position = false;
-                    break;
+
+        let mut delta_time = delta_time.min(MAX_FRAME_TIME);
+        if let Some(playback) = &mut self.playback {
+            if playback.paused {
+                if playback.step_requested {
+                    playback.step_requested = false;
+                    self.step_until_move();
                 }
+                return;
             }
-            
-            if valid_position {
-                break;
-            }
-            
-            enemy_x = rng.random_range(1..self.width - 1);
-            enemy_y = rng.random_range(1..self.height - 1);
-            attempts += 1;
-            if attempts > 100 {
-                // Fallback: spawn at a safe corner
-                enemy_x = self.width / 2;
-                enemy_y = self.height / 2;
-                break;
-            }
+            delta_time *= playback.speed;
+        }
+
+        // Run as many fixed ticks as the elapsed time covers and carry the rest
+        self.tick_accumulator += delta_time;
+        while self.tick_accumulator >= TICK_SECONDS && !self.sim.is_game_over() {
+            self.tick_accumulator -= TICK_SECONDS;
+            self.tick();
         }
-        
-        self.enemies.push(Enemy::new(enemy_x, enemy_y));
     }
 
-    fn add_food(&mut self) {
-        let mut rng = rng();
-        let mut new_x = rng.random_range(1..self.width - 1);
-        let mut new_y = rng.random_range(1..self.height - 1);
-        let mut attempts = 0;
-        loop {
-            let mut valid_position = true;
-            
-            // Check snake1 collision
-            if self.snake1.overlap_tail(new_x, new_y) {
-                valid_position = false;
-            }
-            
-            // Check snake2 collision
-            if self.snake2.overlap_tail(new_x, new_y) {
-                valid_position = false;
-            }
-            
-            // Check enemy collisions
-            for enemy in &self.enemies {
-                if enemy.check_collision(new_x, new_y) {
-                    valid_position = false;
-                    break;
-                }
-            }
-            
-            if valid_position {
+    // Advances a paused playback to the next frame where something visibly moves
+    fn step_until_move(&mut self) {
+        while !self.sim.is_game_over() {
+            if self.tick().snakes_moved {
                 break;
             }
-            
-            new_x = rng.random_range(1..self.width - 1);
-            new_y = rng.random_range(1..self.height - 1);
-            attempts += 1;
-            if attempts > 100 {
-                break; // Prevent infinite loop
-            }
         }
-        self.food_x = new_x;
-        self.food_y = new_y;
-        self.food_exist = true;
     }
 
-    fn update_snake1(&mut self, dir: Option<Direction>) {
-        if self.check_if_snake_alive(&self.snake1, &self.snake2, dir) {
-            self.snake1.move_forward(dir);
-            self.check_eating();
-        } else {
-            self.game_over = true;
-            self.final_score = self.score1.max(self.score2);
-            self.final_level = self.level;
-            if matches!(self.game_mode, crate::menu::GameMode::Survival) {
-                let max_score = self.score1.max(self.score2);
-                if max_score > self.high_score {
-                    self.high_score = max_score;
+    fn tick(&mut self) -> TickEvents {
+        let inputs = match &mut self.playback {
+            Some(playback) => playback.next_inputs(),
+            None => {
+                // Controllers press their keys just like the players; a
+                // replay records the turns, not who made them
+                let players = self.sim.snakes().len();
+                for (player, controller) in self.controllers.iter_mut().enumerate().take(players) {
+                    if let Some(controller) = controller {
+                        self.pending[player] = next_turn(controller.as_mut(), &self.sim, player);
+                    }
                 }
+                TickInputs { directions: std::mem::take(&mut self.pending) }
             }
-        }
-    }
-    
-    fn update_snake2(&mut self, dir: Option<Direction>) {
-        if self.check_if_snake_alive(&self.snake2, &self.snake1, dir) {
-            self.snake2.move_forward(dir);
-            self.check_eating();
-        } else {
-            self.game_over = true;
-            self.final_score = self.score1.max(self.score2);
-            self.final_level = self.level;
-            if matches!(self.game_mode, crate::menu::GameMode::Survival) {
-                let max_score = self.score1.max(self.score2);
-                if max_score > self.high_score {
-                    self.high_score = max_score;
-                }
+        };
+        let events = self.sim.step(&inputs);
+
+        if self.playback.is_none() {
+            self.replay.record(inputs);
+            if events.game_over
+                && let Err(err) = self.replay.save()
+            {
+                eprintln!("Could not save replay: {}", err);
             }
         }
+        events
     }
 
-    pub fn should_return_to_menu(&self) -> bool {
-        self.game_over && self.waiting_time > RESTART_TIME
-    }
-    
-    pub fn get_score(&self) -> i32 {
-        self.score1.max(self.score2)
-    }
-    
-    pub fn get_level(&self) -> i32 {
-        self.level
+    pub fn seed(&self) -> u64 {
+        self.sim.seed()
     }
-    
-    pub fn is_game_over(&self) -> bool {
-        self.game_over
+
+    pub fn should_return_to_menu(&self) -> bool {
+        self.sim.is_game_over() && self.waiting_time > RESTART_TIME
     }
-    
-    fn draw_game_over(&self, con: &Context, g: &mut G2d) {
+
+    fn draw_game_over(&self, layout: &Layout, g: &mut G2d) {
+        let width = self.sim.width();
+        let height = self.sim.height();
+
         // Draw semi-transparent overlay
-        draw_rectangle(GAMEOVER_COLOR, 0, 0, self.width, self.height, con, g);
-        
-        let center_y = self.height / 2;
-        
-        // Draw simple "GAME OVER" indicator - just a big red X pattern
-        let go_color: Color = [1.0, 0.0, 0.0, 1.0];
-        let go_size = 5;
-        let go_x = self.width / 2 - go_size / 2;
-        let go_y = center_y - 3;
-        
-        // Draw X pattern
-        for i in 0..go_size {
-            draw_block(go_color, go_x + i, go_y + i, con, g);
-            draw_block(go_color, go_x + go_size - 1 - i, go_y + i, con, g);
-        }
-        
-        // Draw Player 1 final score
-        let score1_y = center_y + 2;
-        let score1_color: Color = [1.0, 1.0, 0.0, 1.0]; // Yellow
-        let score1_blocks = (self.score1 / 10).min(12);
-        for i in 0..score1_blocks {
-            draw_block(score1_color, 3 + i, score1_y, con, g);
-        }
-        
-        // Draw Player 2 final score
-        let score2_y = center_y + 4;
-        let score2_color: Color = [1.0, 0.5, 0.0, 1.0]; // Orange
-        let score2_blocks = (self.score2 / 10).min(12);
-        for i in 0..score2_blocks {
-            draw_block(score2_color, 3 + i, score2_y, con, g);
-        }
-        
-        // Draw final level with label indicator
-        let level_y = center_y + 5;
-        let level_label_color: Color = [0.0, 0.5, 1.0, 1.0];
-        // Draw "L" indicator for Level
-        for i in 0..4 {
-            draw_block(level_label_color, 2, level_y + i, con, g);
-        }
-        draw_block(level_label_color, 3, level_y + 3, con, g);
-        draw_block(level_label_color, 4, level_y + 3, con, g);
-        
-        // Draw level blocks
-        let level_color: Color = [0.0, 0.5, 1.0, 1.0];
-        let level_blocks_count = self.final_level.min(12);
-        for i in 0..level_blocks_count {
-            draw_block(level_color, 5 + i, level_y + 1, con, g);
+        draw_rectangle(GAMEOVER_COLOR, 0, 0, width, height, layout, g);
+
+        let center_x = width as f64 / 2.0;
+        let center_y = height as f64 / 2.0;
+
+        let title = if self.sim.goal_reached() { "CLEARED" } else { "GAME OVER" };
+        draw_text_centered([1.0, 1.0, 1.0, 1.0], title, center_x, center_y - 6.0, TEXT_LARGE, layout, g);
+
+        // Per-player results
+        let scores = self.sim.scores();
+        let mut row_y = center_y;
+        for (player, (score, snake)) in scores.iter().zip(self.sim.snakes()).enumerate() {
+            let text = format!("P{}  {:>5}  LEN {:>3}", player + 1, score, snake.len());
+            draw_text_centered(SCORE_COLORS[player], &text, center_x, row_y, TEXT_NORMAL, layout, g);
+            row_y += 1.5;
         }
-        
-        // Show high score for survival mode - simple indicator
-        if matches!(self.game_mode, crate::menu::GameMode::Survival) && self.high_score > 0 {
-            let hs_y = level_y + 4;
-            let hs_color: Color = [1.0, 0.8, 0.0, 1.0];
-            // Draw "H" indicator for High Score
-            draw_block(hs_color, 2, hs_y, con, g);
-            draw_block(hs_color, 4, hs_y, con, g);
-            draw_block(hs_color, 2, hs_y + 1, con, g);
-            draw_block(hs_color, 4, hs_y + 1, con, g);
-            draw_block(hs_color, 2, hs_y + 2, con, g);
-            draw_block(hs_color, 3, hs_y + 2, con, g);
-            draw_block(hs_color, 4, hs_y + 2, con, g);
-            draw_block(hs_color, 2, hs_y + 3, con, g);
-            draw_block(hs_color, 4, hs_y + 3, con, g);
-            draw_block(hs_color, 2, hs_y + 4, con, g);
-            draw_block(hs_color, 4, hs_y + 4, con, g);
-            
-            let hs_blocks = (self.high_score / 10).min(12);
-            for i in 0..hs_blocks {
-                draw_block(hs_color, 5 + i, hs_y + 2, con, g);
+
+        let (verdict, verdict_color) = match (self.sim.player_mode(), self.sim.result()) {
+            (PlayerMode::Solo, _) => (format!("SCORE {}", scores[0]), [1.0, 1.0, 1.0, 1.0]),
+            (PlayerMode::CoOp, _) => (format!("TEAM SCORE {}", self.sim.team_score()), TEAM_COLOR),
+            (PlayerMode::Versus, Some(MatchResult::Winner(player))) => {
+                (format!("PLAYER {} WINS", player + 1), SCORE_COLORS[player])
             }
+            (PlayerMode::Versus, _) => ("DRAW".to_string(), [1.0, 1.0, 1.0, 1.0]),
+        };
+        draw_text_centered(verdict_color, &verdict, center_x, center_y + 3.5, TEXT_NORMAL, layout, g);
+
+        draw_text_centered(LEVEL_COLOR, &format!("LEVEL {}", self.sim.final_level()), center_x, center_y + 5.5, TEXT_NORMAL, layout, g);
+
+        // Show the mode's high score, including this match
+        let high_score = self.best_score.max(self.result_score());
+        if high_score > 0 {
+            let text = format!("HIGH SCORE {}", high_score);
+            draw_text_centered(HIGH_SCORE_COLOR, &text, center_x, center_y + 7.0, TEXT_NORMAL, layout, g);
         }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,87 @@
+//! Gamepad input. D-pad hats, the left analog stick and a couple of face
+//! buttons are turned into the same actions the keyboard bindings produce.
+
+use std::collections::HashMap;
+
+use piston_window::{Button, ControllerAxisArgs, ControllerAxisEvent, Event, HatState, PressEvent};
+
+use crate::controls::Action;
+
+// Raw joystick button numbers on common Xbox-layout pads
+const CONFIRM_BUTTON: u8 = 0; // A
+const PAUSE_BUTTON: u8 = 7; // Start
+
+// Left stick
+const STICK_X_AXIS: u8 = 0;
+const STICK_Y_AXIS: u8 = 1;
+
+#[derive(Default)]
+struct Stick {
+    x: f64,
+    y: f64,
+    held: Option<Action>, // Direction the stick is pushed in right now
+}
+
+/// Tracks analog sticks so holding one in a direction counts as a single
+/// press, like a d-pad.
+#[derive(Default)]
+pub struct Gamepads {
+    sticks: HashMap<u32, Stick>,
+}
+
+impl Gamepads {
+    /// The gamepad id and action for `event`: a button or d-pad press, or a
+    /// stick newly pushed past `deadzone`.
+    pub fn action(&mut self, event: &Event, deadzone: f64) -> Option<(u32, Action)> {
+        match event.press_args() {
+            Some(Button::Controller(button)) => {
+                let action = match button.button {
+                    CONFIRM_BUTTON => Action::Confirm,
+                    PAUSE_BUTTON => Action::Pause,
+                    _ => return None,
+                };
+                return Some((button.id, action));
+            }
+            Some(Button::Hat(hat)) => return hat_action(hat.state).map(|action| (hat.id, action)),
+            _ => {}
+        }
+
+        let args = event.controller_axis_args()?;
+        self.stick_moved(args, deadzone).map(|action| (args.id, action))
+    }
+
+    fn stick_moved(&mut self, args: ControllerAxisArgs, deadzone: f64) -> Option<Action> {
+        let stick = self.sticks.entry(args.id).or_default();
+        match args.axis {
+            STICK_X_AXIS => stick.x = args.position,
+            STICK_Y_AXIS => stick.y = args.position,
+            _ => return None,
+        }
+
+        // Inside the deadzone the stick counts as centred; outside it the
+        // stronger axis decides the direction
+        let pushed = if stick.x.abs().max(stick.y.abs()) <= deadzone {
+            None
+        } else if stick.x.abs() > stick.y.abs() {
+            Some(if stick.x > 0.0 { Action::Right } else { Action::Left })
+        } else {
+            Some(if stick.y > 0.0 { Action::Down } else { Action::Up })
+        };
+
+        if pushed == stick.held {
+            return None;
+        }
+        stick.held = pushed;
+        pushed
+    }
+}
+
+fn hat_action(state: HatState) -> Option<Action> {
+    match state {
+        HatState::Up => Some(Action::Up),
+        HatState::Down => Some(Action::Down),
+        HatState::Left => Some(Action::Left),
+        HatState::Right => Some(Action::Right),
+        _ => None, // Centred and diagonals
+    }
+}
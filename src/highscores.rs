@@ -0,0 +1,123 @@
+//! On-disk leaderboard: the best ten results for every game mode.
+//!
+//! Stored as one tab-separated line per entry:
+//! `MODE  score  level  date  name  seed`.
+
+use std::fs;
+use std::io;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::mode::GameMode;
+
+pub const HIGH_SCORE_FILE: &str = "highscores.txt";
+pub const TABLE_SIZE: usize = 10;
+pub const NAME_LENGTH: usize = 3;
+
+#[derive(Clone)]
+pub struct ScoreEntry {
+    pub mode: GameMode,
+    pub score: i32,
+    pub level: i32,
+    pub date: String, // YYYY-MM-DD
+    pub name: String,
+    pub seed: u64,
+}
+
+pub struct HighScores {
+    entries: Vec<ScoreEntry>,
+}
+
+impl HighScores {
+    /// Reads the table from `HIGH_SCORE_FILE`. A missing file is an empty
+    /// table; malformed lines are skipped.
+    pub fn load() -> HighScores {
+        let text = fs::read_to_string(HIGH_SCORE_FILE).unwrap_or_default();
+        let mut scores = HighScores { entries: Vec::new() };
+        for entry in text.lines().filter_map(parse_entry) {
+            scores.insert(entry);
+        }
+        scores
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let mut text = String::new();
+        for entry in &self.entries {
+            text.push_str(&format!(
+                "{}\t{}\t{}\t{}\t{}\t{}\n",
+                entry.mode.get_name(), entry.score, entry.level, entry.date, entry.name, entry.seed
+            ));
+        }
+        fs::write(HIGH_SCORE_FILE, text)
+    }
+
+    /// Entries for `mode`, best first.
+    pub fn table(&self, mode: GameMode) -> Vec<&ScoreEntry> {
+        self.entries.iter().filter(|entry| entry.mode == mode).collect()
+    }
+
+    pub fn best(&self, mode: GameMode) -> i32 {
+        self.table(mode).first().map_or(0, |entry| entry.score)
+    }
+
+    /// Whether `score` would make it into the table for `mode`.
+    pub fn qualifies(&self, mode: GameMode, score: i32) -> bool {
+        if score <= 0 {
+            return false;
+        }
+        let table = self.table(mode);
+        table.len() < TABLE_SIZE || table.last().is_some_and(|last| score > last.score)
+    }
+
+    pub fn insert(&mut self, entry: ScoreEntry) {
+        let mode = entry.mode;
+        // Ties keep the older entry ahead
+        let position = self.entries
+            .iter()
+            .position(|existing| existing.mode == mode && existing.score < entry.score)
+            .unwrap_or(self.entries.len());
+        self.entries.insert(position, entry);
+
+        // Drop whatever fell off the bottom of this mode's table
+        let mut kept = 0;
+        self.entries.retain(|existing| {
+            if existing.mode != mode {
+                return true;
+            }
+            kept += 1;
+            kept <= TABLE_SIZE
+        });
+    }
+}
+
+fn parse_entry(line: &str) -> Option<ScoreEntry> {
+    let mut fields = line.split('\t');
+    Some(ScoreEntry {
+        mode: GameMode::from_name(fields.next()?)?,
+        score: fields.next()?.parse().ok()?,
+        level: fields.next()?.parse().ok()?,
+        date: fields.next()?.to_string(),
+        name: fields.next()?.to_string(),
+        seed: fields.next()?.parse().ok()?,
+    })
+}
+
+/// Today's UTC date as YYYY-MM-DD.
+pub fn today() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let days = (secs / 86_400) as i64;
+
+    // Days since 1970-01-01 to a civil date (Howard Hinnant's algorithm)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
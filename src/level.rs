@@ -0,0 +1,402 @@
+//! Maps: board size, inner walls, where each player starts, where enemies
+//! appear and where food may not.
+//!
+//! Files are plain text. A header of `name value` lines is followed by
+//! `map` and one line per board row, border included, where `#` is a wall
+//! and `.` is open:
+//!
+//! ```text
+//! snake-level 1
+//! name CROSS
+//! size 30 30
+//! spawn 1 8 14 RIGHT
+//! enemies 3 3 6 6
+//! no-food 12 12 6 6
+//! map
+//! ##############################
+//! #............................#
+//! ...
+//! ```
+//!
+//! `spawn <player> <x> <y> <facing>` and the `<x> <y> <width> <height>`
+//! zones are optional; players without a spawn line use the usual starting
+//! spots. The outer ring is always the border, whatever the map row says.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::sim::MAX_PLAYERS;
+use crate::snake::Direction;
+
+pub const LEVEL_DIR: &str = "levels";
+
+/// Board size limits in blocks, border included.
+pub const MIN_BOARD_SIZE: i32 = 20;
+pub const MAX_BOARD_WIDTH: i32 = 80;
+pub const MAX_BOARD_HEIGHT: i32 = 60;
+pub const DEFAULT_BOARD_SIZE: i32 = 30;
+
+/// Clamps a requested board size to the supported range.
+pub fn clamp_board_size(width: i32, height: i32) -> (i32, i32) {
+    (width.clamp(MIN_BOARD_SIZE, MAX_BOARD_WIDTH), height.clamp(MIN_BOARD_SIZE, MAX_BOARD_HEIGHT))
+}
+
+const MAGIC: &str = "snake-level";
+const VERSION: u32 = 1;
+
+// Blocks in a freshly spawned snake, head included
+const SPAWN_LENGTH: i32 = 3;
+
+/// Where a snake starts: its head and the way it faces. The body trails
+/// behind it.
+#[derive(Copy, Clone, PartialEq)]
+pub struct Spawn {
+    pub x: i32,
+    pub y: i32,
+    pub direction: Direction,
+}
+
+impl Spawn {
+    /// Cells the snake covers when it spawns, head first.
+    pub fn cells(&self) -> impl Iterator<Item = (i32, i32)> + '_ {
+        let (dx, dy) = self.direction.offset();
+        (0..SPAWN_LENGTH).map(move |i| (self.x - dx * i, self.y - dy * i))
+    }
+}
+
+/// A rectangle of cells.
+#[derive(Copy, Clone, PartialEq)]
+pub struct Zone {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl Zone {
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        x >= self.x && y >= self.y && x < self.x + self.width && y < self.y + self.height
+    }
+}
+
+#[derive(Clone)]
+pub struct Level {
+    pub name: String,
+    width: i32,
+    height: i32,
+    walls: Vec<bool>, // One per cell, row by row
+    pub spawns: [Spawn; MAX_PLAYERS],
+    pub enemy_zones: Vec<Zone>, // Enemies appear only in these, anywhere when empty
+    pub no_food_zones: Vec<Zone>,
+}
+
+impl Level {
+    /// An empty `width` x `height` board with the usual starting spots.
+    pub fn open(width: i32, height: i32) -> Level {
+        let center_x = width / 2;
+        let center_y = height / 2;
+        // Players 2 and 4 keep their tails off the border on small boards
+        let spawns = [
+            // Player 1: left side, facing right
+            Spawn { x: center_x - 6, y: center_y, direction: Direction::Right },
+            // Player 2: right side, facing left
+            Spawn { x: (center_x + 8).min(width - 4), y: center_y, direction: Direction::Left },
+            // Players 3 and 4: top and bottom, facing each other
+            Spawn { x: center_x, y: center_y - 6, direction: Direction::Down },
+            Spawn { x: center_x, y: (center_y + 8).min(height - 4), direction: Direction::Up },
+        ];
+
+        Level {
+            name: "OPEN".to_string(),
+            width,
+            height,
+            walls: vec![false; (width * height) as usize],
+            spawns,
+            enemy_zones: Vec::new(),
+            no_food_zones: Vec::new(),
+        }
+    }
+
+    pub fn width(&self) -> i32 {
+        self.width
+    }
+
+    pub fn height(&self) -> i32 {
+        self.height
+    }
+
+    /// Whether (x, y) is an inner wall. The border is not counted.
+    pub fn is_wall(&self, x: i32, y: i32) -> bool {
+        self.is_inside(x, y) && self.walls[(y * self.width + x) as usize]
+    }
+
+    pub fn set_wall(&mut self, x: i32, y: i32, wall: bool) {
+        if self.is_inside(x, y) {
+            self.walls[(y * self.width + x) as usize] = wall;
+        }
+    }
+
+    /// Positions of every inner wall.
+    pub fn walls(&self) -> impl Iterator<Item = (i32, i32)> + '_ {
+        (0..self.height)
+            .flat_map(move |y| (0..self.width).map(move |x| (x, y)))
+            .filter(|&(x, y)| self.is_wall(x, y))
+    }
+
+    /// Whether food may be placed at (x, y).
+    pub fn allows_food(&self, x: i32, y: i32) -> bool {
+        !self.is_wall(x, y) && !self.no_food_zones.iter().any(|zone| zone.contains(x, y))
+    }
+
+    /// Whether (x, y) is inside the border.
+    pub fn is_inside(&self, x: i32, y: i32) -> bool {
+        x > 0 && y > 0 && x < self.width - 1 && y < self.height - 1
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = format!("{} {}\nname {}\nsize {} {}\n", MAGIC, VERSION, self.name, self.width, self.height);
+        for (player, spawn) in self.spawns.iter().enumerate() {
+            text.push_str(&format!(
+                "spawn {} {} {} {}\n",
+                player + 1,
+                spawn.x,
+                spawn.y,
+                direction_name(spawn.direction)
+            ));
+        }
+        for (key, zones) in [("enemies", &self.enemy_zones), ("no-food", &self.no_food_zones)] {
+            for zone in zones {
+                text.push_str(&format!("{} {} {} {} {}\n", key, zone.x, zone.y, zone.width, zone.height));
+            }
+        }
+
+        text.push_str("map\n");
+        for y in 0..self.height {
+            let row: String = (0..self.width)
+                .map(|x| if self.is_wall(x, y) || !self.is_inside(x, y) { '#' } else { '.' })
+                .collect();
+            text.push_str(&row);
+            text.push('\n');
+        }
+        text
+    }
+
+    pub fn parse(text: &str) -> io::Result<Level> {
+        let mut lines = text.lines();
+        let version: u32 = lines.next()
+            .and_then(|line| line.strip_prefix(MAGIC))
+            .and_then(|rest| rest.trim().parse().ok())
+            .ok_or_else(|| invalid("not a snake level"))?;
+        if version != VERSION {
+            return Err(invalid("made for another version of the game"));
+        }
+
+        let mut name = String::new();
+        let mut level: Option<Level> = None;
+        let mut spawns: Vec<(usize, Spawn)> = Vec::new();
+        let mut enemy_zones = Vec::new();
+        let mut no_food_zones = Vec::new();
+        for line in lines.by_ref() {
+            if line == "map" {
+                break;
+            }
+            let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+            let numbers: Vec<i32> = value.split_whitespace().filter_map(|n| n.parse().ok()).collect();
+            match key {
+                "name" => name = value.trim().to_string(),
+                "size" => match numbers[..] {
+                    [width, height] if clamp_board_size(width, height) == (width, height) => {
+                        level = Some(Level::open(width, height));
+                    }
+                    _ => return Err(invalid("bad size")),
+                },
+                "spawn" => {
+                    let facing = value.split_whitespace().nth(3).and_then(parse_direction);
+                    match (&numbers[..], facing) {
+                        ([player, x, y], Some(direction)) if (1..=MAX_PLAYERS as i32).contains(player) => {
+                            let player = *player as usize - 1;
+                            if spawns.iter().any(|(other, _)| *other == player) {
+                                return Err(invalid(&format!("player {} spawns twice", player + 1)));
+                            }
+                            spawns.push((player, Spawn { x: *x, y: *y, direction }));
+                        }
+                        _ => return Err(invalid("bad spawn")),
+                    }
+                }
+                "enemies" | "no-food" => {
+                    let zone = match numbers[..] {
+                        [x, y, width, height] if width > 0 && height > 0 => Zone { x, y, width, height },
+                        _ => return Err(invalid(&format!("bad {} zone", key))),
+                    };
+                    if key == "enemies" {
+                        enemy_zones.push(zone);
+                    } else {
+                        no_food_zones.push(zone);
+                    }
+                }
+                "" => {}
+                _ => return Err(invalid(&format!("unknown line {}", key))),
+            }
+        }
+
+        let mut level = level.ok_or_else(|| invalid("missing size"))?;
+        if !name.is_empty() {
+            level.name = name;
+        }
+        let fits = |zone: &Zone| {
+            level.is_inside(zone.x, zone.y) && level.is_inside(zone.x + zone.width - 1, zone.y + zone.height - 1)
+        };
+        if !enemy_zones.iter().chain(&no_food_zones).all(fits) {
+            return Err(invalid("zone reaches past the border"));
+        }
+        level.enemy_zones = enemy_zones;
+        level.no_food_zones = no_food_zones;
+
+        let rows: Vec<&str> = lines.collect();
+        if rows.len() != level.height as usize {
+            return Err(invalid("map has the wrong number of rows"));
+        }
+        for (y, row) in rows.iter().enumerate() {
+            if row.chars().count() != level.width as usize {
+                return Err(invalid("map row has the wrong length"));
+            }
+            for (x, cell) in row.chars().enumerate() {
+                let wall = match cell {
+                    '#' => true,
+                    '.' => false,
+                    _ => return Err(invalid(&format!("unknown tile {}", cell))),
+                };
+                level.set_wall(x as i32, y as i32, wall);
+            }
+        }
+
+        for (player, spawn) in spawns {
+            if spawn.cells().any(|(x, y)| !level.is_inside(x, y) || level.is_wall(x, y)) {
+                return Err(invalid(&format!("player {} spawns in a wall", player + 1)));
+            }
+            level.spawns[player] = spawn;
+        }
+        for (player, spawn) in level.spawns.iter().enumerate() {
+            for (other, other_spawn) in level.spawns.iter().enumerate().take(player) {
+                if spawn.cells().any(|cell| other_spawn.cells().any(|other_cell| other_cell == cell)) {
+                    return Err(invalid(&format!("players {} and {} spawn on top of each other", other + 1, player + 1)));
+                }
+            }
+        }
+        Ok(level)
+    }
+
+    pub fn load(path: &Path) -> io::Result<Level> {
+        Level::parse(&fs::read_to_string(path)?)
+    }
+
+    /// Level files in `LEVEL_DIR`, by name.
+    pub fn list() -> Vec<PathBuf> {
+        let mut files: Vec<PathBuf> = match fs::read_dir(LEVEL_DIR) {
+            Ok(entries) => entries
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .filter(|path| path.extension().is_some_and(|ext| ext == "txt"))
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+        files.sort();
+        files
+    }
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+fn direction_name(direction: Direction) -> &'static str {
+    match direction {
+        Direction::Up => "UP",
+        Direction::Down => "DOWN",
+        Direction::Left => "LEFT",
+        Direction::Right => "RIGHT",
+    }
+}
+
+fn parse_direction(name: &str) -> Option<Direction> {
+    match name {
+        "UP" => Some(Direction::Up),
+        "DOWN" => Some(Direction::Down),
+        "LEFT" => Some(Direction::Left),
+        "RIGHT" => Some(Direction::Right),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A level file for a `width` x `height` board with `header` lines
+    // before the map
+    fn file(width: i32, height: i32, header: &str) -> String {
+        let mut text = format!("{} {}\nsize {} {}\n{}map\n", MAGIC, VERSION, width, height, header);
+        for y in 0..height {
+            let row: String = (0..width)
+                .map(|x| if x == 0 || y == 0 || x == width - 1 || y == height - 1 { '#' } else { '.' })
+                .collect();
+            text.push_str(&row);
+            text.push('\n');
+        }
+        text
+    }
+
+    fn error(text: &str) -> String {
+        Level::parse(text).err().map(|err| err.to_string()).unwrap_or_default()
+    }
+
+    #[test]
+    fn parses_its_own_output() {
+        let level = Level::parse(&file(30, 20, "spawn 1 5 5 RIGHT\nenemies 10 10 3 3\n")).unwrap();
+        assert_eq!((level.width(), level.height()), (30, 20));
+        assert_eq!(Level::parse(&level.to_text()).unwrap().to_text(), level.to_text());
+    }
+
+    #[test]
+    fn rejects_bad_size() {
+        let text = file(30, 30, "").replace("size 30 30", "size 30");
+        assert_eq!(error(&text), "bad size");
+        let text = file(30, 30, "").replace("size 30 30", "size thirty 30");
+        assert_eq!(error(&text), "bad size");
+    }
+
+    #[test]
+    fn rejects_sizes_outside_the_limits() {
+        let (width, height) = (MIN_BOARD_SIZE - 1, MAX_BOARD_HEIGHT + 1);
+        assert_ne!(clamp_board_size(width, height), (width, height));
+        assert_eq!(error(&file(width, MIN_BOARD_SIZE, "")), "bad size");
+        assert_eq!(error(&file(MIN_BOARD_SIZE, height, "")), "bad size");
+        assert!(Level::parse(&file(MAX_BOARD_WIDTH, MAX_BOARD_HEIGHT, "")).is_ok());
+    }
+
+    #[test]
+    fn rejects_unknown_tiles() {
+        let text = file(30, 30, "").replacen("#.", "#?", 1);
+        assert_eq!(error(&text), "unknown tile ?");
+    }
+
+    #[test]
+    fn rejects_too_many_spawns() {
+        let players = format!("spawn {} 5 5 RIGHT\n", MAX_PLAYERS + 1);
+        assert_eq!(error(&file(30, 30, &players)), "bad spawn");
+        assert_eq!(error(&file(30, 30, "spawn 1 5 5 RIGHT\nspawn 1 5 9 RIGHT\n")), "player 1 spawns twice");
+    }
+
+    #[test]
+    fn rejects_overlapping_spawns() {
+        let same_cell = "spawn 1 5 5 RIGHT\nspawn 2 5 5 DOWN\n";
+        assert_eq!(error(&file(30, 30, same_cell)), "players 1 and 2 spawn on top of each other");
+        // Player 2's head on player 1's tail
+        let tail = "spawn 1 8 5 RIGHT\nspawn 2 6 5 UP\n";
+        assert_eq!(error(&file(30, 30, tail)), "players 1 and 2 spawn on top of each other");
+        // Player 3 left at its usual spot, under player 1's body
+        let usual = Level::open(30, 30).spawns[2];
+        let onto_usual = format!("spawn 1 {} {} LEFT\n", usual.x, usual.y);
+        assert_eq!(error(&file(30, 30, &onto_usual)), "players 1 and 3 spawn on top of each other");
+    }
+}
@@ -0,0 +1,59 @@
+//! The snake game as a library: the headless `sim` rules, the piston
+//! frontend the `snake` binary is built from, and the `SnakeController`
+//! trait for steering snakes from your own code.
+//!
+//! A controller gets an `Observation` of the board each tick and answers
+//! with the way its snake should go:
+//!
+//! ```
+//! use snake::{Direction, Observation, Registry, SnakeController};
+//!
+//! // Turns right whenever the cell ahead is a wall
+//! struct Turner;
+//!
+//! impl SnakeController for Turner {
+//!     fn choose(&mut self, observation: &Observation) -> Direction {
+//!         let (x, y) = observation.head();
+//!         let (dx, dy) = observation.heading().offset();
+//!         if observation.is_wall(x + dx, y + dy) {
+//!             match observation.heading() {
+//!                 Direction::Up => Direction::Right,
+//!                 Direction::Right => Direction::Down,
+//!                 Direction::Down => Direction::Left,
+//!                 Direction::Left => Direction::Up,
+//!             }
+//!         } else {
+//!             observation.heading()
+//!         }
+//!     }
+//! }
+//!
+//! let mut registry = Registry::default();
+//! registry.register("TURNER", || Box::new(Turner));
+//! assert!(registry.create("TURNER").is_some());
+//! ```
+
+pub mod draw;
+pub mod snake;
+pub mod game;
+pub mod menu;
+pub mod mode;
+pub mod enemy;
+pub mod pathfind;
+pub mod ai;
+pub mod controller;
+pub mod sim;
+pub mod replay;
+pub mod highscores;
+pub mod font;
+pub mod controls;
+pub mod settings;
+pub mod gamepad;
+pub mod food;
+pub mod powerup;
+pub mod level;
+pub mod editor;
+pub mod campaign;
+
+pub use controller::{Observation, Registry, SnakeController};
+pub use snake::Direction;
@@ -1,88 +1,537 @@
-extern crate rand;
 extern crate piston_window;
 
-mod draw;
-mod snake;
-mod game;
-mod menu;
-mod enemy;
-
 use piston_window::*;
 use piston_window::types::Color;
-use game::Game;
-use menu::{Menu, GameState};
+use snake::game::Game;
+use std::collections::VecDeque;
+
+use snake::menu::{Menu, MenuItem, GameState, ReplayBrowser, ScoreBoard, InitialsPrompt, ControlsScreen, PauseMenu, PauseItem, LevelSelect};
+use snake::replay::Replay;
+use snake::highscores::HighScores;
+use snake::mode::GameMode;
+use snake::controls::Action;
+use snake::settings::Settings;
+use snake::gamepad::Gamepads;
+use snake::level::Level;
+use snake::editor::Editor;
+use snake::campaign::{Progress, STAGES};
+use snake::mode::PlayerMode;
+use snake::sim::MAX_PLAYERS;
 
-use crate::draw::to_coord_u32;
+use snake::draw::{fit_to_window, window_to_cell, Layout, DEFAULT_BLOCK_SIZE};
+use snake::game::PANEL_COLUMNS;
 
 const BLACK_COLOR: Color = [0.1, 0.1, 0.1, 1.0];
 
+// Menus and other screens are laid out on a grid this many blocks square
+const SCREEN_SIZE: i32 = 30;
+// The main menu's list runs a few rows further down
+const MENU_ROWS: i32 = SCREEN_SIZE + 3;
+
+// Largest window opened at start-up, in pixels. Bigger boards get smaller
+// blocks; the window can be resized afterwards.
+const MAX_WINDOW_WIDTH: f64 = 1600.0;
+const MAX_WINDOW_HEIGHT: f64 = 1000.0;
+
+// The value after `name` on the command line, e.g. `--seed 42`
+fn arg_value(name: &str) -> Option<String> {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == name {
+            return args.next();
+        }
+    }
+    None
+}
+
+// Reads `--seed <n>` from the command line
+fn seed_from_args() -> Option<u64> {
+    arg_value("--seed").and_then(|value| value.parse().ok())
+}
+
+// Reads `--size <width>x<height>` from the command line
+fn size_from_args() -> Option<(i32, i32)> {
+    let value = arg_value("--size")?.to_lowercase();
+    let (width, height) = value.split_once('x')?;
+    Some(snake::level::clamp_board_size(width.parse().ok()?, height.parse().ok()?))
+}
+
+// The layout for the board of `game` with the side panel next to it
+fn game_view(game: &Game, con: &Context) -> Layout {
+    fit_to_window(game.sim().width() + PANEL_COLUMNS, game.sim().height(), con)
+}
+
+// A campaign match on `stage`: solo on Medium, until its goal is met
+fn start_stage(stage: usize, menu: &Menu, settings: &Settings, window: &mut PistonWindow) -> Game {
+    let level = STAGES[stage].level().unwrap_or_else(|err| {
+        eprintln!("Could not load the map for stage {}: {}", stage + 1, err);
+        Level::open(settings.board_width, settings.board_height)
+    });
+    let mut config = menu.match_config(GameMode::Medium, &level);
+    config.player_mode = PlayerMode::Solo;
+    config.wrap_walls = false;
+    config.goal = Some(STAGES[stage].goal);
+
+    let mut game = Game::new(config, level);
+    game.set_controls(settings.controls.clone());
+    window.set_title(format!("Snake - Campaign stage {}", stage + 1));
+    game
+}
+
 fn main() {
-    let (width, height) = (30, 30); // Bigger field
-    // Make window wider to accommodate score/level display
-    let window_width = to_coord_u32(width + 12); // Extra space for score/level blocks
-    let window_height = to_coord_u32(height);
+    let mut settings = Settings::load();
+    if let Some((width, height)) = size_from_args() {
+        // Only for this run; settings.txt keeps its own size
+        settings.board_width = width;
+        settings.board_height = height;
+    }
 
-    let mut window: PistonWindow = WindowSettings::new("Snake", [window_width, window_height])
-        .exit_on_esc(true)
+    // Open the window to fit the board and side panel
+    let columns = (settings.board_width + PANEL_COLUMNS) as f64;
+    let rows = settings.board_height as f64;
+    let block = DEFAULT_BLOCK_SIZE.min(MAX_WINDOW_WIDTH / columns).min(MAX_WINDOW_HEIGHT / rows);
+    let mut window: PistonWindow = WindowSettings::new("Snake", [(columns * block) as u32, (rows * block) as u32])
+        .resizable(true)
         .build()
         .unwrap();
 
     let mut menu = Menu::new();
+    menu.seed = seed_from_args();
     let mut game_state = GameState::Menu;
     let mut game: Option<Game> = None;
+    let mut replay_browser = ReplayBrowser::open();
+    let mut high_scores = HighScores::load();
+    let mut score_board = ScoreBoard::new(GameMode::Medium);
+    let mut initials: VecDeque<InitialsPrompt> = VecDeque::new();
+    let mut controls_screen = ControlsScreen::new(settings.clone());
+    let mut controls_return = GameState::Menu; // Where the controls screen goes back to
+    let mut pause_menu = PauseMenu::new();
+    let mut editor = Editor::new(Level::open(settings.board_width, settings.board_height), None);
+    let mut game_return = GameState::Menu; // Where a finished or abandoned match goes back to
+    let mut progress = Progress::load();
+    let mut level_select = LevelSelect::new(0);
+    let mut gamepads = Gamepads::default();
 
     while let Some(event) = window.next() {
+        let pad = gamepads.action(&event, settings.deadzone);
         match game_state {
             GameState::Menu => {
+                let key = match event.press_args() {
+                    Some(Button::Keyboard(key)) => Some(key),
+                    _ => None,
+                };
+                let action = key.and_then(|key| settings.controls.menu_action(key)).or(pad.map(|(_, action)| action));
+                match action {
+                    Some(Action::Up) => menu.select_prev(),
+                    Some(Action::Down) => menu.select_next(),
+                    Some(Action::Left) => menu.prev_player_mode(),
+                    Some(Action::Right) => menu.next_player_mode(),
+                    Some(Action::Confirm) => match menu.get_selected_item() {
+                        MenuItem::Play(mode) => {
+                            // Start game with selected mode, on the chosen map
+                            let level = match menu.map_path() {
+                                Some(path) => Level::load(path).unwrap_or_else(|err| {
+                                    eprintln!("Could not load level {}: {}", path.display(), err);
+                                    Level::open(settings.board_width, settings.board_height)
+                                }),
+                                None => Level::open(settings.board_width, settings.board_height),
+                            };
+                            let mut new_game = Game::new(menu.match_config(mode, &level), level);
+                            new_game.set_best_score(high_scores.best(mode));
+                            new_game.set_controls(settings.controls.clone());
+                            for player in 0..MAX_PLAYERS {
+                                new_game.set_controller(player, menu.controller(player));
+                            }
+                            window.set_title(format!(
+                                "Snake - {} {} (seed {})",
+                                mode.get_name(), menu.player_mode.get_name(), new_game.seed()
+                            ));
+                            game = Some(new_game);
+                            game_return = GameState::Menu;
+                            game_state = GameState::Playing;
+                        }
+                        MenuItem::Player(player) => menu.cycle_bot(player),
+                        MenuItem::Campaign => {
+                            // Start on the furthest stage reached
+                            level_select = LevelSelect::new((0..STAGES.len()).rfind(|&stage| progress.is_unlocked(stage)).unwrap_or(0));
+                            game_state = GameState::Campaign;
+                        }
+                        MenuItem::Map => menu.cycle_map(),
+                        MenuItem::Editor => {
+                            let path = menu.map_path().map(|path| path.to_path_buf());
+                            let level = match &path {
+                                Some(path) => Level::load(path).unwrap_or_else(|err| {
+                                    eprintln!("Could not load level {}: {}", path.display(), err);
+                                    Level::open(settings.board_width, settings.board_height)
+                                }),
+                                None => Level::open(settings.board_width, settings.board_height),
+                            };
+                            editor = Editor::new(level, path);
+                            game_state = GameState::Editor;
+                        }
+                        MenuItem::Corpses => menu.toggle_corpse_rule(),
+                        MenuItem::Survivor => menu.toggle_survivor_rule(),
+                        MenuItem::InputBuffer => menu.cycle_input_buffer(),
+                        MenuItem::Controls => {
+                            controls_screen = ControlsScreen::new(settings.clone());
+                            controls_return = GameState::Menu;
+                            game_state = GameState::Controls;
+                        }
+                        MenuItem::Replays => {
+                            replay_browser = ReplayBrowser::open();
+                            game_state = GameState::Replays;
+                        }
+                        MenuItem::HighScores => game_state = GameState::HighScores,
+                        MenuItem::Quit => window.set_should_close(true),
+                    },
+                    _ => match key {
+                        Some(Key::Backspace) => menu.clear_seed(),
+                        Some(Key::Tab) => menu.toggle_wrap_walls(),
+                        Some(key) => {
+                            // Number keys type in a fixed seed
+                            let code = key.code();
+                            if (Key::D0.code()..=Key::D9.code()).contains(&code) {
+                                menu.push_seed_digit((code - Key::D0.code()) as u64);
+                            }
+                        }
+                        None => {}
+                    },
+                }
+                
+                window.draw_2d(&event, |c, g, _device| {
+                    clear(BLACK_COLOR, g);
+                    menu.draw(SCREEN_SIZE, MENU_ROWS, &fit_to_window(SCREEN_SIZE, MENU_ROWS, &c), g);
+                });
+            }
+            
+            GameState::Replays => {
                 if let Some(Button::Keyboard(key)) = event.press_args() {
                     match key {
-                        Key::Up => menu.select_prev(),
-                        Key::Down => menu.select_next(),
+                        Key::Up => replay_browser.select_prev(),
+                        Key::Down => replay_browser.select_next(),
                         Key::Return => {
-                            // Start game with selected mode
-                            game = Some(Game::new(width, height, menu.get_selected_mode()));
-                            game_state = GameState::Playing;
+                            if let Some(path) = replay_browser.selected_path() {
+                                match Replay::load(path) {
+                                    Ok(replay) => {
+                                        window.set_title(format!(
+                                            "Snake - Replay {} {} (seed {})",
+                                            replay.config.game_mode.get_name(), replay.config.player_mode.get_name(), replay.config.seed
+                                        ));
+                                        game = Some(Game::from_replay(replay));
+                                        game_return = GameState::Menu;
+                                        game_state = GameState::Playing;
+                                    }
+                                    Err(err) => eprintln!("Could not load replay {}: {}", path.display(), err),
+                                }
+                            }
                         }
+                        Key::Backspace | Key::Escape => game_state = GameState::Menu,
                         _ => {}
                     }
                 }
-                
+
                 window.draw_2d(&event, |c, g, _device| {
                     clear(BLACK_COLOR, g);
-                    menu.draw(width, height, &c, g);
+                    replay_browser.draw(SCREEN_SIZE, SCREEN_SIZE, &fit_to_window(SCREEN_SIZE, SCREEN_SIZE, &c), g);
                 });
             }
-            
+
             GameState::Playing => {
                 if let Some(ref mut game) = game {
-                    if let Some(Button::Keyboard(key)) = event.press_args() {
+                    // Live matches pause on Esc, either player's pause key or
+                    // Start, and on their own when the window loses focus
+                    let can_pause = !game.is_playback() && !game.sim().is_game_over();
+                    let key = match event.press_args() {
+                        Some(Button::Keyboard(key)) => Some(key),
+                        _ => None,
+                    };
+                    let pause_pressed = key == Some(Key::Escape)
+                        || key.and_then(|key| settings.controls.action(key)).is_some_and(|(_, action)| action == Action::Pause)
+                        || pad.is_some_and(|(_, action)| action == Action::Pause);
+                    if can_pause && (pause_pressed || event.focus_args() == Some(false)) {
+                        pause_menu = PauseMenu::new();
+                        game_state = GameState::Paused;
+                        continue;
+                    }
+
+                    if let Some(key) = key {
+                        if game.is_playback() && matches!(key, Key::Backspace | Key::Escape) {
+                            // Leave the replay early
+                            game_state = GameState::Menu;
+                            continue;
+                        }
                         game.key_pressed(key);
                     }
+                    if let Some((id, action)) = pad
+                        && let Some(player) = settings.gamepad_player(id)
+                    {
+                        game.player_action(player, action);
+                    }
                     
                     window.draw_2d(&event, |c, g, _device| {
                         clear(BLACK_COLOR, g);
-                        game.draw(&c, g);
+                        game.draw(&game_view(game, &c), g);
                     });
 
                     event.update(|arg| {
                         game.update(arg.dt);
                         if game.should_return_to_menu() {
                             game_state = GameState::GameOver;
+
+                            if game_return == GameState::Campaign
+                                && game.sim().goal_reached()
+                                && progress.clear(level_select.selected())
+                                && let Err(err) = progress.save()
+                            {
+                                eprintln!("Could not save campaign progress: {}", err);
+                            }
+
+                            // Ask each player (or the co-op team) who made the leaderboard
+                            // for initials. Play-tests from the editor don't count.
+                            if !game.is_playback() && game_return == GameState::Menu {
+                                let sim = game.sim();
+                                let mode = sim.game_mode();
+                                for (label, color, score) in game.results() {
+                                    if high_scores.qualifies(mode, score) {
+                                        initials.push_back(InitialsPrompt::new(label, color, mode, score, sim.final_level(), sim.seed()));
+                                    }
+                                }
+                                if !initials.is_empty() {
+                                    game_state = GameState::EnterInitials;
+                                }
+                            }
                         }
                     });
                 }
             }
             
+            GameState::Paused => {
+                // Nothing calls `Game::update` here, so every timer stands still
+                if let Some(ref mut current_game) = game {
+                    let key = match event.press_args() {
+                        Some(Button::Keyboard(key)) => Some(key),
+                        _ => None,
+                    };
+                    let action = key.and_then(|key| settings.controls.menu_action(key)).or(pad.map(|(_, action)| action));
+                    match action {
+                        _ if key == Some(Key::Escape) => game_state = GameState::Playing,
+                        Some(Action::Pause) => game_state = GameState::Playing,
+                        Some(Action::Up) => pause_menu.select_prev(),
+                        Some(Action::Down) => pause_menu.select_next(),
+                        Some(Action::Confirm) => match pause_menu.get_selected_item() {
+                            PauseItem::Resume => game_state = GameState::Playing,
+                            PauseItem::Restart => {
+                                current_game.restart_game();
+                                game_state = GameState::Playing;
+                            }
+                            PauseItem::Settings => {
+                                controls_screen = ControlsScreen::new(settings.clone());
+                                controls_return = GameState::Paused;
+                                game_state = GameState::Controls;
+                            }
+                            PauseItem::QuitToMenu => {
+                                game_state = game_return;
+                                game = None;
+                                continue;
+                            }
+                        },
+                        _ => {}
+                    }
+
+                    window.draw_2d(&event, |c, g, _device| {
+                        clear(BLACK_COLOR, g);
+                        let view = game_view(current_game, &c);
+                        current_game.draw(&view, g);
+                        pause_menu.draw(&settings.controls, current_game.sim().width(), current_game.sim().height(), &view, g);
+                    });
+                }
+            }
+
+            GameState::EnterInitials => {
+                if let Some(prompt) = initials.front_mut()
+                    && let Some(Button::Keyboard(key)) = event.press_args()
+                {
+                    let code = key.code();
+                    match key {
+                        Key::Backspace => prompt.pop_letter(),
+                        Key::Return if !prompt.is_empty() => {
+                            let prompt = initials.pop_front().unwrap();
+                            score_board = ScoreBoard::new(prompt.mode());
+                            high_scores.insert(prompt.into_entry());
+                            if let Err(err) = high_scores.save() {
+                                eprintln!("Could not save high scores: {}", err);
+                            }
+                            if initials.is_empty() {
+                                game_state = GameState::HighScores;
+                                game = None;
+                            }
+                        }
+                        _ if (Key::A.code()..=Key::Z.code()).contains(&code) => {
+                            if let Some(letter) = char::from_u32(code as u32) {
+                                prompt.push_letter(letter);
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+
+                if let Some(prompt) = initials.front() {
+                    window.draw_2d(&event, |c, g, _device| {
+                        clear(BLACK_COLOR, g);
+                        prompt.draw(SCREEN_SIZE, SCREEN_SIZE, &fit_to_window(SCREEN_SIZE, SCREEN_SIZE, &c), g);
+                    });
+                }
+            }
+
+            GameState::Controls => {
+                if let Some(Button::Keyboard(key)) = event.press_args() {
+                    if controls_screen.is_capturing() {
+                        if matches!(key, Key::Backspace | Key::Escape) {
+                            controls_screen.cancel_capture();
+                        } else {
+                            controls_screen.capture(key);
+                        }
+                    } else {
+                        match key {
+                            Key::Up => controls_screen.select_prev(),
+                            Key::Down => controls_screen.select_next(),
+                            Key::Return => controls_screen.activate(),
+                            Key::Delete => controls_screen.reset_defaults(),
+                            Key::Backspace | Key::Escape if controls_screen.try_finish() => {
+                                settings = controls_screen.settings().clone();
+                                if let Err(err) = settings.save() {
+                                    eprintln!("Could not save settings: {}", err);
+                                }
+                                if let Some(ref mut current_game) = game {
+                                    current_game.set_controls(settings.controls.clone());
+                                }
+                                game_state = controls_return;
+                            }
+                            _ => {}
+                        }
+                    }
+                } else if !controls_screen.is_capturing() {
+                    match pad.map(|(_, action)| action) {
+                        Some(Action::Up) => controls_screen.select_prev(),
+                        Some(Action::Down) => controls_screen.select_next(),
+                        Some(Action::Confirm) => controls_screen.activate(),
+                        _ => {}
+                    }
+                }
+
+                window.draw_2d(&event, |c, g, _device| {
+                    clear(BLACK_COLOR, g);
+                    controls_screen.draw(SCREEN_SIZE, SCREEN_SIZE, &fit_to_window(SCREEN_SIZE, SCREEN_SIZE, &c), g);
+                });
+            }
+
+            GameState::Editor => {
+                let columns = editor.level().width() + PANEL_COLUMNS;
+                let rows = editor.level().height();
+                if let Some(position) = event.mouse_cursor_args() {
+                    let size = window.size();
+                    let (x, y) = window_to_cell(position, [size.width, size.height], columns, rows);
+                    editor.point_at(x, y);
+                }
+                if let Some(Button::Mouse(_)) = event.release_args() {
+                    editor.stop_painting();
+                }
+
+                match event.press_args() {
+                    Some(Button::Mouse(MouseButton::Left)) => editor.start_painting(false),
+                    Some(Button::Mouse(MouseButton::Right)) => editor.start_painting(true),
+                    Some(Button::Keyboard(key)) => match key {
+                        Key::Up => editor.move_cursor(0, -1),
+                        Key::Down => editor.move_cursor(0, 1),
+                        Key::Left => editor.move_cursor(-1, 0),
+                        Key::Right => editor.move_cursor(1, 0),
+                        Key::Space | Key::Return => editor.apply(),
+                        Key::Tab => editor.next_tool(),
+                        Key::Delete => editor.erase(),
+                        Key::S if editor.save() => menu.refresh_maps(editor.path()),
+                        Key::T if editor.validate() => {
+                            // Play-test the level as it stands, saved or not
+                            let level = editor.level().clone();
+                            let mut new_game = Game::new(menu.match_config(GameMode::Medium, &level), level);
+                            new_game.set_controls(settings.controls.clone());
+                            window.set_title(format!("Snake - Play-test {}", editor.level().name));
+                            game = Some(new_game);
+                            game_return = GameState::Editor;
+                            game_state = GameState::Playing;
+                        }
+                        Key::Backspace | Key::Escape => game_state = GameState::Menu,
+                        _ => {}
+                    },
+                    _ => {}
+                }
+
+                window.draw_2d(&event, |c, g, _device| {
+                    clear(BLACK_COLOR, g);
+                    editor.draw(&fit_to_window(columns, rows, &c), g);
+                });
+            }
+
+            GameState::Campaign => {
+                let key = match event.press_args() {
+                    Some(Button::Keyboard(key)) => Some(key),
+                    _ => None,
+                };
+                let action = key.and_then(|key| settings.controls.menu_action(key)).or(pad.map(|(_, action)| action));
+                match action {
+                    Some(Action::Up) => level_select.move_by(0, -1),
+                    Some(Action::Down) => level_select.move_by(0, 1),
+                    Some(Action::Left) => level_select.move_by(-1, 0),
+                    Some(Action::Right) => level_select.move_by(1, 0),
+                    Some(Action::Confirm) if progress.is_unlocked(level_select.selected()) => {
+                        game = Some(start_stage(level_select.selected(), &menu, &settings, &mut window));
+                        game_return = GameState::Campaign;
+                        game_state = GameState::Playing;
+                    }
+                    _ if matches!(key, Some(Key::Backspace | Key::Escape)) => game_state = GameState::Menu,
+                    _ => {}
+                }
+
+                window.draw_2d(&event, |c, g, _device| {
+                    clear(BLACK_COLOR, g);
+                    level_select.draw(&progress, SCREEN_SIZE, SCREEN_SIZE, &fit_to_window(SCREEN_SIZE, SCREEN_SIZE, &c), g);
+                });
+            }
+
+            GameState::HighScores => {
+                if let Some(Button::Keyboard(key)) = event.press_args() {
+                    match key {
+                        Key::Left => score_board.prev_mode(),
+                        Key::Right => score_board.next_mode(),
+                        Key::Return | Key::Backspace | Key::Escape => game_state = GameState::Menu,
+                        _ => {}
+                    }
+                }
+
+                window.draw_2d(&event, |c, g, _device| {
+                    clear(BLACK_COLOR, g);
+                    score_board.draw(&high_scores, SCREEN_SIZE, SCREEN_SIZE, &fit_to_window(SCREEN_SIZE, SCREEN_SIZE, &c), g);
+                });
+            }
+
             GameState::GameOver => {
                 if let Some(ref mut current_game) = game {
                     if let Some(Button::Keyboard(_key)) = event.press_args() {
-                        // Return to menu on any key press
-                        game_state = GameState::Menu;
+                        // A cleared campaign stage goes straight on to the next one
+                        let next_stage = level_select.selected() + 1;
+                        if game_return == GameState::Campaign && current_game.sim().goal_reached() && next_stage < STAGES.len() {
+                            level_select = LevelSelect::new(next_stage);
+                            game = Some(start_stage(next_stage, &menu, &settings, &mut window));
+                            game_state = GameState::Playing;
+                            continue;
+                        }
+
+                        // Otherwise return to menu (or the editor or level select) on any key press
+                        game_state = game_return;
                         game = None;
                     } else {
                         window.draw_2d(&event, |c, g, _device| {
                             clear(BLACK_COLOR, g);
-                            current_game.draw(&c, g);
+                            current_game.draw(&game_view(current_game, &c), g);
                         });
 
                         event.update(|arg| {
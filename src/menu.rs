@@ -1,167 +1,843 @@
-use piston_window::types::Color;
-use crate::draw::{draw_block, draw_rectangle};
+use std::path::{Path, PathBuf};
 
-#[derive(Copy, Clone, PartialEq)]
-pub enum GameMode {
-    Easy,
-    Medium,
-    Hard,
-    Timer,      // Play for 60 seconds
-    Survival,   // Endless mode with high score tracking
-}
-
-impl GameMode {
-    pub fn get_base_speed(&self) -> f64 {
-        match self {
-            GameMode::Easy => 0.4,       // Slower start
-            GameMode::Medium => 0.3,    // Medium start
-            GameMode::Hard => 0.2,      // Faster start
-            GameMode::Timer => 0.3,     // Medium speed for timer mode
-            GameMode::Survival => 0.3,   // Medium speed for survival mode
-        }
-    }
-    
-    pub fn get_speed_multiplier(&self) -> f64 {
-        match self {
-            GameMode::Easy => 0.05,      // Slower acceleration
-            GameMode::Medium => 0.08,   // Medium acceleration
-            GameMode::Hard => 0.12,      // Faster acceleration
-            GameMode::Timer => 0.08,     // Medium acceleration
-            GameMode::Survival => 0.08,  // Medium acceleration
-        }
-    }
-    
-    pub fn get_name(&self) -> &str {
-        match self {
-            GameMode::Easy => "EASY",
-            GameMode::Medium => "MEDIUM",
-            GameMode::Hard => "HARD",
-            GameMode::Timer => "TIMER",
-            GameMode::Survival => "SURVIVAL",
-        }
-    }
-    
-    pub fn is_timed_mode(&self) -> bool {
-        matches!(self, GameMode::Timer)
-    }
-    
-    pub fn get_time_limit(&self) -> f64 {
-        match self {
-            GameMode::Timer => 60.0,  // 60 seconds
-            _ => 0.0,
-        }
-    }
-}
+use piston_window::Key;
+use piston_window::types::Color;
+use rand::Rng;
+use crate::draw::{draw_rectangle, Layout, draw_text, draw_text_centered, TEXT_LARGE, TEXT_NORMAL, TEXT_SMALL};
+use crate::mode::{CorpseRule, GameMode, PlayerMode, SurvivorRule};
+use crate::campaign::{Progress, STAGES};
+use crate::level::Level;
+use crate::replay::Replay;
+use crate::sim::{MatchConfig, MAX_INPUT_BUFFER, MAX_PLAYERS, MIN_INPUT_BUFFER, TICK_RATE};
+use crate::controller::{Registry, SnakeController};
+use crate::controls::{is_bindable, key_name, Action, Controls, InputDevice, ALL_ACTIONS, KEYBOARD_PLAYERS};
+use crate::settings::Settings;
+use crate::highscores::{HighScores, ScoreEntry, NAME_LENGTH, today};
 
 #[derive(Copy, Clone, PartialEq)]
 pub enum GameState {
     Menu,
     Playing,
     GameOver,
+    Replays,
+    EnterInitials,
+    HighScores,
+    Controls,
+    Paused,
+    Editor,
+    Campaign,
+}
+
+#[derive(Copy, Clone, PartialEq)]
+pub enum MenuItem {
+    Play(GameMode),
+    Player(usize), // Cycles who steers that player's snake: a person or a bot
+    Campaign,
+    Map,      // Cycles through the level files
+    Editor,   // Edits the chosen map, or a new one
+    Corpses,  // Toggles the corpse rule
+    Survivor, // Toggles the survivor rule
+    InputBuffer, // Cycles the turn queue depth
+    Controls,
+    Replays,
+    HighScores,
+    Quit,
 }
 
 pub struct Menu {
-    pub selected_mode: usize,
-    pub modes: Vec<GameMode>,
+    pub selected: usize,
+    pub items: Vec<MenuItem>,
+    pub seed: Option<u64>, // Fixed seed for the next match, random when unset
+    pub player_mode: PlayerMode,
+    pub corpse_rule: CorpseRule,
+    pub survivor_rule: SurvivorRule,
+    pub input_buffer: usize,
+    pub bots: [Option<usize>; MAX_PLAYERS], // Controller per slot, as an index into `controllers`
+    pub controllers: Registry,
+    pub wrap_walls: Vec<GameMode>, // Modes played with wrap-around walls
+    pub maps: Vec<PathBuf>, // Level files found at start-up
+    pub map: Option<usize>, // Index into `maps`, or the open board when unset
+}
+
+impl Default for Menu {
+    fn default() -> Menu {
+        Menu::new()
+    }
 }
 
 impl Menu {
     pub fn new() -> Menu {
         Menu {
-            selected_mode: 1, // Default to Medium
-            modes: vec![
-                GameMode::Easy, 
-                GameMode::Medium, 
-                GameMode::Hard,
-                GameMode::Timer,
-                GameMode::Survival
+            selected: 1, // Default to Medium
+            items: vec![
+                MenuItem::Play(GameMode::Easy),
+                MenuItem::Play(GameMode::Medium),
+                MenuItem::Play(GameMode::Hard),
+                MenuItem::Play(GameMode::Timer),
+                MenuItem::Play(GameMode::Survival),
+                MenuItem::Player(0),
+                MenuItem::Player(1),
+                MenuItem::Campaign,
+                MenuItem::Map,
+                MenuItem::Editor,
+                MenuItem::Corpses,
+                MenuItem::Survivor,
+                MenuItem::InputBuffer,
+                MenuItem::Controls,
+                MenuItem::Replays,
+                MenuItem::HighScores,
+                MenuItem::Quit,
             ],
+            seed: None,
+            player_mode: PlayerMode::Versus,
+            corpse_rule: CorpseRule::Remove,
+            survivor_rule: SurvivorRule::WinsNow,
+            input_buffer: 2,
+            bots: [None; MAX_PLAYERS],
+            controllers: Registry::default(),
+            wrap_walls: Vec::new(),
+            maps: Level::list(),
+            map: None,
         }
     }
     
     pub fn select_next(&mut self) {
-        self.selected_mode = (self.selected_mode + 1) % self.modes.len();
+        self.selected = (self.selected + 1) % self.items.len();
     }
     
     pub fn select_prev(&mut self) {
-        if self.selected_mode == 0 {
-            self.selected_mode = self.modes.len() - 1;
+        if self.selected == 0 {
+            self.selected = self.items.len() - 1;
         } else {
-            self.selected_mode -= 1;
+            self.selected -= 1;
         }
     }
     
-    pub fn get_selected_mode(&self) -> GameMode {
-        self.modes[self.selected_mode]
+    pub fn get_selected_item(&self) -> MenuItem {
+        self.items[self.selected]
     }
-    
-    
-    pub fn draw(&self, width: i32, height: i32, con: &piston_window::Context, g: &mut piston_window::G2d) {
+
+    pub fn next_player_mode(&mut self) {
+        self.player_mode = match self.player_mode {
+            PlayerMode::Solo => PlayerMode::Versus,
+            PlayerMode::Versus => PlayerMode::CoOp,
+            PlayerMode::CoOp => PlayerMode::Solo,
+        };
+    }
+
+    pub fn prev_player_mode(&mut self) {
+        self.player_mode = match self.player_mode {
+            PlayerMode::Solo => PlayerMode::CoOp,
+            PlayerMode::Versus => PlayerMode::Solo,
+            PlayerMode::CoOp => PlayerMode::Versus,
+        };
+    }
+
+    pub fn toggle_corpse_rule(&mut self) {
+        self.corpse_rule = match self.corpse_rule {
+            CorpseRule::Remove => CorpseRule::Obstacle,
+            CorpseRule::Obstacle => CorpseRule::Remove,
+        };
+    }
+
+    pub fn toggle_survivor_rule(&mut self) {
+        self.survivor_rule = match self.survivor_rule {
+            SurvivorRule::WinsNow => SurvivorRule::PlaysOn,
+            SurvivorRule::PlaysOn => SurvivorRule::WinsNow,
+        };
+    }
+
+    pub fn cycle_input_buffer(&mut self) {
+        self.input_buffer = if self.input_buffer >= MAX_INPUT_BUFFER {
+            MIN_INPUT_BUFFER
+        } else {
+            self.input_buffer + 1
+        };
+    }
+
+    /// Steps a player slot from human through each registered controller
+    /// and back.
+    pub fn cycle_bot(&mut self, player: usize) {
+        let next = self.bots[player].map_or(0, |index| index + 1);
+        self.bots[player] = (next < self.controllers.len()).then_some(next);
+    }
+
+    /// A fresh controller for `player`'s slot, or None for a human.
+    pub fn controller(&self, player: usize) -> Option<Box<dyn SnakeController>> {
+        let name = self.controllers.names().nth(self.bots[player]?)?;
+        self.controllers.create(name)
+    }
+
+    /// Switches wrap-around walls on or off for the highlighted game mode.
+    pub fn toggle_wrap_walls(&mut self) {
+        if let MenuItem::Play(mode) = self.get_selected_item() {
+            if let Some(index) = self.wrap_walls.iter().position(|m| *m == mode) {
+                self.wrap_walls.remove(index);
+            } else {
+                self.wrap_walls.push(mode);
+            }
+        }
+    }
+
+    pub fn wraps(&self, mode: GameMode) -> bool {
+        self.wrap_walls.contains(&mode)
+    }
+
+    /// Steps to the next level file, then back to the open board.
+    pub fn cycle_map(&mut self) {
+        self.map = match self.map {
+            None if !self.maps.is_empty() => Some(0),
+            Some(index) if index + 1 < self.maps.len() => Some(index + 1),
+            _ => None,
+        };
+    }
+
+    /// The level file picked for the next match, if any.
+    pub fn map_path(&self) -> Option<&Path> {
+        self.map.map(|index| self.maps[index].as_path())
+    }
+
+    /// Re-reads the level files, picking `select` if it is one of them.
+    pub fn refresh_maps(&mut self, select: Option<&Path>) {
+        self.maps = Level::list();
+        self.map = select.and_then(|path| self.maps.iter().position(|map| map == path));
+    }
+
+    pub fn push_seed_digit(&mut self, digit: u64) {
+        let seed = self.seed.unwrap_or(0);
+        self.seed = Some(seed.saturating_mul(10).saturating_add(digit));
+    }
+
+    pub fn clear_seed(&mut self) {
+        self.seed = None;
+    }
+
+    /// Seed for the next match: the one typed in or passed on the command
+    /// line, otherwise a fresh random one.
+    pub fn next_seed(&self) -> u64 {
+        self.seed.unwrap_or_else(|| rand::rng().random())
+    }
+
+    /// Settings for a `mode` match on `level` with the rules picked here.
+    pub fn match_config(&self, mode: GameMode, level: &Level) -> MatchConfig {
+        MatchConfig {
+            width: level.width(),
+            height: level.height(),
+            game_mode: mode,
+            player_mode: self.player_mode,
+            corpse_rule: self.corpse_rule,
+            survivor_rule: self.survivor_rule,
+            input_buffer: self.input_buffer,
+            wrap_walls: self.wraps(mode),
+            goal: None,
+            seed: self.next_seed(),
+        }
+    }
+
+    pub fn draw(&self, width: i32, height: i32, layout: &Layout, g: &mut piston_window::G2d) {
         // Draw semi-transparent background
         let bg_color: Color = [0.0, 0.0, 0.0, 0.8];
-        draw_rectangle(bg_color, 0, 0, width, height, con, g);
-        
+        draw_rectangle(bg_color, 0, 0, width, height, layout, g);
+
+        let center_x = width as f64 / 2.0;
+
         // Draw title
         let title_color: Color = [1.0, 1.0, 1.0, 1.0];
-        let title_y = height / 2 - 6;
-        
-        // Draw "SNAKE" title using blocks
-        let title_blocks = vec![
-            (3, title_y), (4, title_y), (5, title_y), (6, title_y), (7, title_y), // S
-            (9, title_y), (10, title_y), (11, title_y), (12, title_y), (13, title_y), // N
-            (15, title_y), (16, title_y), (17, title_y), // A
-            (3, title_y + 2), (4, title_y + 2), (5, title_y + 2), (6, title_y + 2), (7, title_y + 2), // K
-            (9, title_y + 2), (10, title_y + 2), (11, title_y + 2), (12, title_y + 2), (13, title_y + 2), // E
-        ];
-        
-        for (x, y) in title_blocks {
-            draw_block(title_color, x, y, con, g);
-        }
-        
-        // Draw mode selection
-        let mode_y_start = height / 2 + 2;
-        for (i, mode) in self.modes.iter().enumerate() {
-            let mode_y = mode_y_start + (i as i32 * 3);
-            let is_selected = i == self.selected_mode;
-            
-            let mode_color = if is_selected {
+        draw_text_centered(title_color, "SNAKE", center_x, 1.5, TEXT_LARGE, layout, g);
+
+        // Player count for the next match
+        let players_text = format!("< {} >", self.player_mode.get_name());
+        draw_text_centered([1.0, 0.5, 0.0, 1.0], &players_text, center_x, 5.0, TEXT_NORMAL, layout, g);
+
+        // Draw menu entries
+        let item_y_start = 6.5;
+        for (i, item) in self.items.iter().enumerate() {
+            let item_y = item_y_start + i as f64 * 1.2;
+            let is_selected = i == self.selected;
+
+            let item_color = if is_selected {
                 [0.0, 1.0, 0.0, 1.0] // Green for selected
             } else {
                 [0.7, 0.7, 0.7, 1.0] // Gray for unselected
             };
-            
+
             // Draw selection indicator
             if is_selected {
-                draw_block([1.0, 1.0, 0.0, 1.0], 5, mode_y, con, g); // Yellow arrow
-            }
-            
-            // Draw simple mode indicator - colored blocks instead of text
-            let name_start_x = 7;
-            let mode_indicator = match mode {
-                GameMode::Easy => 1,
-                GameMode::Medium => 2,
-                GameMode::Hard => 3,
-                GameMode::Timer => 4,
-                GameMode::Survival => 5,
-            };
-            // Draw blocks to represent mode
-            for i in 0..mode_indicator {
-                draw_block(mode_color, name_start_x + i, mode_y, con, g);
+                draw_text([1.0, 1.0, 0.0, 1.0], ">", 7.5, item_y, TEXT_NORMAL, layout, g); // Yellow arrow
             }
+            draw_text(item_color, &self.label(*item), 9.0, item_y, TEXT_NORMAL, layout, g);
         }
-        
+
+        // Seed for the next match
+        let seed_text = match self.seed {
+            Some(seed) => format!("SEED {}", seed),
+            None => "SEED RANDOM".to_string(),
+        };
+        draw_text_centered([0.0, 1.0, 1.0, 1.0], &seed_text, center_x, (height - 5) as f64, TEXT_NORMAL, layout, g);
+
         // Draw instructions
         let inst_color: Color = [0.5, 0.5, 0.5, 1.0];
-        let inst_y = height - 3;
-        // "UP/DOWN: Select, ENTER: Start"
-        let inst_text = vec![
-            (3, inst_y), (4, inst_y), (5, inst_y), // UP
-            (7, inst_y), (8, inst_y), (9, inst_y), (10, inst_y), (11, inst_y), // DOWN
-            (13, inst_y), (14, inst_y), (15, inst_y), (16, inst_y), (17, inst_y), // ENTER
-        ];
-        for (x, y) in inst_text {
-            draw_block(inst_color, x, y, con, g);
+        draw_text_centered(inst_color, "UP/DOWN: SELECT  ENTER: START", center_x, (height - 4) as f64, TEXT_SMALL, layout, g);
+        draw_text_centered(inst_color, "LEFT/RIGHT: PLAYERS  TAB: WRAP WALLS", center_x, (height - 3) as f64, TEXT_SMALL, layout, g);
+        draw_text_centered(inst_color, "0-9: TYPE SEED  BACKSPACE: CLEAR", center_x, (height - 2) as f64, TEXT_SMALL, layout, g);
+    }
+
+    fn label(&self, item: MenuItem) -> String {
+        match item {
+            MenuItem::Play(mode) if self.wraps(mode) => format!("{}  WRAP", mode.get_name()),
+            MenuItem::Play(mode) => mode.get_name().to_string(),
+            MenuItem::Map => match self.map_path().and_then(|path| path.file_stem()) {
+                Some(name) => format!("MAP: {}", name.to_string_lossy().to_uppercase()),
+                None => "MAP: OPEN".to_string(),
+            },
+            MenuItem::Player(player) => match self.bots[player].and_then(|index| self.controllers.names().nth(index)) {
+                Some(name) => format!("PLAYER {}: CPU {}", player + 1, name),
+                None => format!("PLAYER {}: HUMAN", player + 1),
+            },
+            MenuItem::Campaign => "CAMPAIGN".to_string(),
+            MenuItem::Editor => "LEVEL EDITOR".to_string(),
+            MenuItem::Corpses => format!("CORPSES: {}", self.corpse_rule.get_name()),
+            MenuItem::Survivor => format!("SURVIVOR: {}", self.survivor_rule.get_name()),
+            MenuItem::InputBuffer => format!("INPUT BUFFER: {}", self.input_buffer),
+            MenuItem::Controls => "SETTINGS".to_string(),
+            MenuItem::Replays => "REPLAYS".to_string(),
+            MenuItem::HighScores => "HIGH SCORES".to_string(),
+            MenuItem::Quit => "QUIT".to_string(),
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq)]
+pub enum PauseItem {
+    Resume,
+    Restart,
+    Settings,
+    QuitToMenu,
+}
+
+const PAUSE_ITEMS: [PauseItem; 4] = [
+    PauseItem::Resume,
+    PauseItem::Restart,
+    PauseItem::Settings,
+    PauseItem::QuitToMenu,
+];
+
+impl PauseItem {
+    pub fn label(&self) -> &str {
+        match self {
+            PauseItem::Resume => "RESUME",
+            PauseItem::Restart => "RESTART",
+            PauseItem::Settings => "SETTINGS",
+            PauseItem::QuitToMenu => "QUIT TO MENU",
+        }
+    }
+}
+
+/// The menu shown over a paused match.
+pub struct PauseMenu {
+    selected: usize,
+}
+
+impl Default for PauseMenu {
+    fn default() -> PauseMenu {
+        PauseMenu::new()
+    }
+}
+
+impl PauseMenu {
+    pub fn new() -> PauseMenu {
+        PauseMenu { selected: 0 }
+    }
+
+    pub fn select_next(&mut self) {
+        self.selected = (self.selected + 1) % PAUSE_ITEMS.len();
+    }
+
+    pub fn select_prev(&mut self) {
+        self.selected = (self.selected + PAUSE_ITEMS.len() - 1) % PAUSE_ITEMS.len();
+    }
+
+    pub fn get_selected_item(&self) -> PauseItem {
+        PAUSE_ITEMS[self.selected]
+    }
+
+    pub fn draw(&self, controls: &Controls, width: i32, height: i32, layout: &Layout, g: &mut piston_window::G2d) {
+        // Dim the frozen match underneath
+        let bg_color: Color = [0.0, 0.0, 0.0, 0.6];
+        draw_rectangle(bg_color, 0, 0, width, height, layout, g);
+
+        let center_x = width as f64 / 2.0;
+        let center_y = height as f64 / 2.0;
+        draw_text_centered([1.0, 1.0, 1.0, 1.0], "PAUSED", center_x, center_y - 7.0, TEXT_LARGE, layout, g);
+
+        for (i, item) in PAUSE_ITEMS.iter().enumerate() {
+            let y = center_y - 2.0 + i as f64 * 2.0;
+            let is_selected = i == self.selected;
+            let color: Color = if is_selected { [0.0, 1.0, 0.0, 1.0] } else { [0.7, 0.7, 0.7, 1.0] };
+            if is_selected {
+                draw_text([1.0, 1.0, 0.0, 1.0], ">", center_x - 6.0, y, TEXT_NORMAL, layout, g); // Yellow arrow
+            }
+            draw_text(color, item.label(), center_x - 4.5, y, TEXT_NORMAL, layout, g);
+        }
+
+        let inst_color: Color = [0.5, 0.5, 0.5, 1.0];
+        draw_text_centered(inst_color, "TO RESUME PRESS", center_x, (height - 3) as f64, TEXT_SMALL, layout, g);
+        draw_text_centered(inst_color, &resume_keys(controls), center_x, (height - 2) as f64, TEXT_SMALL, layout, g);
+    }
+}
+
+// Everything that leaves the pause menu: Esc, each player's pause key and
+// the gamepad's Start button, e.g. `ESC, P, Q OR START`
+fn resume_keys(controls: &Controls) -> String {
+    let mut keys = vec!["ESC".to_string()];
+    for player in 0..KEYBOARD_PLAYERS {
+        let key = key_name(controls.key(player, Action::Pause));
+        if !keys.contains(&key) {
+            keys.push(key);
         }
     }
+    format!("{} OR START", keys.join(", "))
 }
 
+/// Lists saved replays, newest first, and picks one to watch.
+pub struct ReplayBrowser {
+    entries: Vec<(PathBuf, Replay)>,
+    selected: usize,
+}
+
+impl ReplayBrowser {
+    pub fn open() -> ReplayBrowser {
+        // Unreadable files are left out rather than failing the whole list
+        let entries = Replay::list()
+            .into_iter()
+            .filter_map(|path| Replay::load(&path).ok().map(|replay| (path, replay)))
+            .collect();
+        ReplayBrowser { entries, selected: 0 }
+    }
+
+    pub fn select_next(&mut self) {
+        if !self.entries.is_empty() {
+            self.selected = (self.selected + 1) % self.entries.len();
+        }
+    }
+
+    pub fn select_prev(&mut self) {
+        if !self.entries.is_empty() {
+            self.selected = (self.selected + self.entries.len() - 1) % self.entries.len();
+        }
+    }
+
+    pub fn selected_path(&self) -> Option<&Path> {
+        self.entries.get(self.selected).map(|(path, _)| path.as_path())
+    }
+
+    pub fn draw(&self, width: i32, height: i32, layout: &Layout, g: &mut piston_window::G2d) {
+        let bg_color: Color = [0.0, 0.0, 0.0, 0.8];
+        draw_rectangle(bg_color, 0, 0, width, height, layout, g);
+
+        let text_color: Color = [1.0, 1.0, 1.0, 1.0];
+        draw_text_centered(text_color, "REPLAYS", width as f64 / 2.0, 1.0, TEXT_NORMAL, layout, g);
+        if self.entries.is_empty() {
+            draw_text_centered([0.7, 0.7, 0.7, 1.0], "NO REPLAYS YET", width as f64 / 2.0, 4.0, TEXT_NORMAL, layout, g);
+        }
+
+        // One row per replay: mode, seed and length
+        let visible = ((height - 6) / 2).max(1) as usize;
+        let first = self.selected.saturating_sub(visible - 1);
+        for (row, (_, replay)) in self.entries.iter().enumerate().skip(first).take(visible) {
+            let y = 3.0 + (row - first) as f64 * 1.5;
+            let is_selected = row == self.selected;
+            if is_selected {
+                draw_text([1.0, 1.0, 0.0, 1.0], ">", 1.0, y, TEXT_NORMAL, layout, g); // Yellow arrow
+            }
+            let color: Color = if is_selected { [0.0, 1.0, 1.0, 1.0] } else { [0.7, 0.7, 0.7, 1.0] };
+            let seconds = replay.inputs.len() as u32 / TICK_RATE;
+            let text = format!(
+                "{:<8} {:<9} {:>3}:{:02}  SEED {}",
+                replay.config.game_mode.get_name(), replay.config.player_mode.get_name(),
+                seconds / 60, seconds % 60, replay.config.seed
+            );
+            draw_text(color, &text, 2.5, y, TEXT_SMALL, layout, g);
+        }
+
+        let inst_color: Color = [0.5, 0.5, 0.5, 1.0];
+        draw_text_centered(inst_color, "ENTER: WATCH  BACKSPACE: BACK", width as f64 / 2.0, (height - 2) as f64, TEXT_SMALL, layout, g);
+    }
+}
+
+const ALL_MODES: [GameMode; 5] = [
+    GameMode::Easy,
+    GameMode::Medium,
+    GameMode::Hard,
+    GameMode::Timer,
+    GameMode::Survival,
+];
+
+/// Shows the leaderboard for one mode at a time.
+pub struct ScoreBoard {
+    mode_index: usize,
+}
+
+impl ScoreBoard {
+    pub fn new(mode: GameMode) -> ScoreBoard {
+        ScoreBoard {
+            mode_index: ALL_MODES.iter().position(|m| *m == mode).unwrap_or(0),
+        }
+    }
+
+    pub fn next_mode(&mut self) {
+        self.mode_index = (self.mode_index + 1) % ALL_MODES.len();
+    }
+
+    pub fn prev_mode(&mut self) {
+        self.mode_index = (self.mode_index + ALL_MODES.len() - 1) % ALL_MODES.len();
+    }
+
+    pub fn draw(&self, scores: &HighScores, width: i32, height: i32, layout: &Layout, g: &mut piston_window::G2d) {
+        let bg_color: Color = [0.0, 0.0, 0.0, 0.8];
+        draw_rectangle(bg_color, 0, 0, width, height, layout, g);
+
+        let center_x = width as f64 / 2.0;
+        let mode = ALL_MODES[self.mode_index];
+        draw_text_centered([1.0, 1.0, 1.0, 1.0], "HIGH SCORES", center_x, 1.0, TEXT_NORMAL, layout, g);
+        draw_text_centered([0.5, 0.0, 1.0, 1.0], &format!("< {} >", mode.get_name()), center_x, 2.5, TEXT_NORMAL, layout, g);
+
+        // One line per entry: rank, name, score, level, date
+        let score_color: Color = [1.0, 0.8, 0.0, 1.0];
+        let table = scores.table(mode);
+        if table.is_empty() {
+            draw_text_centered([0.7, 0.7, 0.7, 1.0], "NO SCORES YET", center_x, 5.0, TEXT_NORMAL, layout, g);
+        }
+        for (rank, entry) in table.iter().enumerate() {
+            let text = format!(
+                "{:>2} {:<3} {:>6} L{:<2} {}",
+                rank + 1, entry.name, entry.score, entry.level, entry.date
+            );
+            draw_text(score_color, &text, 2.0, 5.0 + rank as f64 * 1.5, TEXT_SMALL, layout, g);
+        }
+
+        let inst_color: Color = [0.5, 0.5, 0.5, 1.0];
+        draw_text_centered(inst_color, "LEFT/RIGHT: MODE  ENTER: BACK", center_x, (height - 2) as f64, TEXT_SMALL, layout, g);
+    }
+}
+
+// Level select layout, in blocks
+const STAGE_COLUMNS: usize = 4;
+const STAGE_CELL_WIDTH: i32 = 5;
+const STAGE_CELL_HEIGHT: i32 = 4;
+const STAGE_GAP: i32 = 2;
+
+/// Grid of campaign stages. Locked ones can't be picked; cleared ones are
+/// shown in green.
+pub struct LevelSelect {
+    selected: usize,
+}
+
+impl LevelSelect {
+    pub fn new(selected: usize) -> LevelSelect {
+        LevelSelect { selected: selected.min(STAGES.len() - 1) }
+    }
+
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    /// Moves the selection by `columns` and `rows` cells, staying on the grid.
+    pub fn move_by(&mut self, columns: i32, rows: i32) {
+        let index = self.selected as i32 + columns + rows * STAGE_COLUMNS as i32;
+        if (0..STAGES.len() as i32).contains(&index) {
+            self.selected = index as usize;
+        }
+    }
+
+    pub fn draw(&self, progress: &Progress, width: i32, height: i32, layout: &Layout, g: &mut piston_window::G2d) {
+        let bg_color: Color = [0.0, 0.0, 0.0, 0.8];
+        draw_rectangle(bg_color, 0, 0, width, height, layout, g);
+
+        let center_x = width as f64 / 2.0;
+        draw_text_centered([1.0, 1.0, 1.0, 1.0], "CAMPAIGN", center_x, 1.0, TEXT_NORMAL, layout, g);
+
+        let columns = STAGE_COLUMNS as i32;
+        let left = (width - columns * STAGE_CELL_WIDTH - (columns - 1) * STAGE_GAP) / 2;
+        for stage in 0..STAGES.len() {
+            let x = left + (stage % STAGE_COLUMNS) as i32 * (STAGE_CELL_WIDTH + STAGE_GAP);
+            let y = 4 + (stage / STAGE_COLUMNS) as i32 * (STAGE_CELL_HEIGHT + STAGE_GAP);
+            let (cell_color, number_color): (Color, Color) = if stage == self.selected {
+                ([1.0, 1.0, 0.0, 1.0], [0.0, 0.0, 0.0, 1.0]) // Yellow
+            } else if progress.is_cleared(stage) {
+                ([0.2, 0.6, 0.2, 1.0], [1.0, 1.0, 1.0, 1.0]) // Green
+            } else if progress.is_unlocked(stage) {
+                ([0.2, 0.3, 0.7, 1.0], [1.0, 1.0, 1.0, 1.0]) // Blue
+            } else {
+                ([0.25, 0.25, 0.25, 1.0], [0.45, 0.45, 0.45, 1.0]) // Grey
+            };
+            draw_rectangle(cell_color, x, y, STAGE_CELL_WIDTH, STAGE_CELL_HEIGHT, layout, g);
+            let number_x = x as f64 + STAGE_CELL_WIDTH as f64 / 2.0;
+            draw_text_centered(number_color, &(stage + 1).to_string(), number_x, y as f64 + 0.5, TEXT_LARGE, layout, g);
+        }
+
+        // Details of the selected stage
+        let stage = &STAGES[self.selected];
+        let map_name = stage.map.map_or("OPEN".to_string(), |file| file.trim_end_matches(".txt").to_uppercase());
+        let details_y = 4.0 + (STAGES.len().div_ceil(STAGE_COLUMNS) as i32 * (STAGE_CELL_HEIGHT + STAGE_GAP)) as f64;
+        draw_text_centered([0.5, 0.0, 1.0, 1.0], &format!("MAP {}", map_name), center_x, details_y, TEXT_NORMAL, layout, g);
+        let (goal_text, goal_color): (String, Color) = if progress.is_unlocked(self.selected) {
+            (stage.goal.describe(), [0.0, 1.0, 1.0, 1.0])
+        } else {
+            ("LOCKED".to_string(), [0.6, 0.6, 0.6, 1.0])
+        };
+        draw_text_centered(goal_color, &goal_text, center_x, details_y + 1.5, TEXT_NORMAL, layout, g);
+
+        let inst_color: Color = [0.5, 0.5, 0.5, 1.0];
+        draw_text_centered(inst_color, "ARROWS: SELECT  ENTER: PLAY", center_x, (height - 3) as f64, TEXT_SMALL, layout, g);
+        draw_text_centered(inst_color, "BACKSPACE: BACK", center_x, (height - 2) as f64, TEXT_SMALL, layout, g);
+    }
+}
+
+/// Collects a player's (or a co-op team's) initials for a new leaderboard entry.
+pub struct InitialsPrompt {
+    label: String, // "PLAYER 1", "TEAM", ...
+    color: Color,
+    mode: GameMode,
+    score: i32,
+    level: i32,
+    seed: u64,
+    name: String,
+}
+
+impl InitialsPrompt {
+    pub fn new(label: String, color: Color, mode: GameMode, score: i32, level: i32, seed: u64) -> InitialsPrompt {
+        InitialsPrompt {
+            label,
+            color,
+            mode,
+            score,
+            level,
+            seed,
+            name: String::new(),
+        }
+    }
+
+    pub fn push_letter(&mut self, letter: char) {
+        if self.name.len() < NAME_LENGTH {
+            self.name.push(letter.to_ascii_uppercase());
+        }
+    }
+
+    pub fn pop_letter(&mut self) {
+        self.name.pop();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.name.is_empty()
+    }
+
+    pub fn mode(&self) -> GameMode {
+        self.mode
+    }
+
+    pub fn into_entry(self) -> ScoreEntry {
+        ScoreEntry {
+            mode: self.mode,
+            score: self.score,
+            level: self.level,
+            date: today(),
+            name: self.name,
+            seed: self.seed,
+        }
+    }
+
+    pub fn draw(&self, width: i32, height: i32, layout: &Layout, g: &mut piston_window::G2d) {
+        let bg_color: Color = [0.0, 0.0, 0.0, 0.8];
+        draw_rectangle(bg_color, 0, 0, width, height, layout, g);
+
+        let center_x = width as f64 / 2.0;
+        let center_y = height as f64 / 2.0;
+        draw_text_centered([1.0, 0.8, 0.0, 1.0], "NEW HIGH SCORE!", center_x, center_y - 6.0, TEXT_NORMAL, layout, g);
+        let result = format!("{}  {}", self.label, self.score);
+        draw_text_centered(self.color, &result, center_x, center_y - 3.0, TEXT_NORMAL, layout, g);
+
+        // Typed letters, with underscores for the rest
+        let mut name = self.name.clone();
+        while name.len() < NAME_LENGTH {
+            name.push('_');
+        }
+        draw_text_centered([1.0, 1.0, 1.0, 1.0], &name, center_x, center_y, TEXT_LARGE, layout, g);
+
+        let inst_color: Color = [0.5, 0.5, 0.5, 1.0];
+        draw_text_centered(inst_color, "TYPE INITIALS  ENTER: SAVE", center_x, center_y + 6.0, TEXT_SMALL, layout, g);
+    }
+}
+
+// One line of the controls screen
+#[derive(Copy, Clone)]
+enum ControlsRow {
+    Device(usize),
+    Binding(usize, Action),
+    Deadzone,
+    BoardWidth,
+    BoardHeight,
+}
+
+/// Rebinds each player's keys and picks the device they steer with, plus
+/// the stick deadzone and the board size for new matches. Keys
+/// bound to more than one action, or a gamepad given to both players, are
+/// shown in red and the screen can't be left until they are sorted out.
+pub struct ControlsScreen {
+    settings: Settings,
+    rows: Vec<ControlsRow>,
+    selected: usize,
+    capturing: bool, // Waiting for the new key for the selected row
+    warn_conflicts: bool,
+}
+
+impl ControlsScreen {
+    pub fn new(settings: Settings) -> ControlsScreen {
+        let mut rows = Vec::new();
+        for player in 0..KEYBOARD_PLAYERS {
+            rows.push(ControlsRow::Device(player));
+            rows.extend(ALL_ACTIONS.iter().map(|action| ControlsRow::Binding(player, *action)));
+        }
+        rows.extend([ControlsRow::Deadzone, ControlsRow::BoardWidth, ControlsRow::BoardHeight]);
+
+        ControlsScreen {
+            settings,
+            rows,
+            selected: 1, // Player 1's first key
+            capturing: false,
+            warn_conflicts: false,
+        }
+    }
+
+    pub fn settings(&self) -> &Settings {
+        &self.settings
+    }
+
+    pub fn is_capturing(&self) -> bool {
+        self.capturing
+    }
+
+    pub fn select_next(&mut self) {
+        self.selected = (self.selected + 1) % self.rows.len();
+    }
+
+    pub fn select_prev(&mut self) {
+        self.selected = (self.selected + self.rows.len() - 1) % self.rows.len();
+    }
+
+    /// Enter on the selected row: wait for a new key, or step to the next
+    /// device, deadzone or board size.
+    pub fn activate(&mut self) {
+        match self.rows[self.selected] {
+            ControlsRow::Device(player) => {
+                self.settings.devices[player] = self.settings.devices[player].next();
+            }
+            ControlsRow::Binding(..) => self.capturing = true,
+            ControlsRow::Deadzone => self.settings.cycle_deadzone(),
+            ControlsRow::BoardWidth => self.settings.cycle_board_width(),
+            ControlsRow::BoardHeight => self.settings.cycle_board_height(),
+        }
+    }
+
+    pub fn cancel_capture(&mut self) {
+        self.capturing = false;
+    }
+
+    /// Binds `key` to the selected row. Reserved keys are ignored and keep
+    /// the screen waiting.
+    pub fn capture(&mut self, key: Key) {
+        if !is_bindable(key) {
+            return;
+        }
+        if let ControlsRow::Binding(player, action) = self.rows[self.selected] {
+            self.settings.controls.bind(player, action, key);
+        }
+        self.capturing = false;
+    }
+
+    pub fn reset_defaults(&mut self) {
+        self.settings = Settings::default();
+    }
+
+    fn device_conflict(&self, player: usize) -> bool {
+        let device = self.settings.devices[player];
+        device != InputDevice::Keyboard && self.settings.devices.iter().filter(|d| **d == device).count() > 1
+    }
+
+    fn row_conflict(&self, row: ControlsRow) -> bool {
+        match row {
+            ControlsRow::Device(player) => self.device_conflict(player),
+            ControlsRow::Binding(player, action) => self.settings.controls.has_conflict(player, action),
+            ControlsRow::Deadzone | ControlsRow::BoardWidth | ControlsRow::BoardHeight => false,
+        }
+    }
+
+    /// Whether the settings are free of conflicts and can be kept. If not,
+    /// a warning is shown instead.
+    pub fn try_finish(&mut self) -> bool {
+        self.warn_conflicts = self.rows.iter().any(|row| self.row_conflict(*row));
+        !self.warn_conflicts
+    }
+
+    pub fn draw(&self, width: i32, height: i32, layout: &Layout, g: &mut piston_window::G2d) {
+        let bg_color: Color = [0.0, 0.0, 0.0, 0.8];
+        draw_rectangle(bg_color, 0, 0, width, height, layout, g);
+
+        let center_x = width as f64 / 2.0;
+        draw_text_centered([1.0, 1.0, 1.0, 1.0], "SETTINGS", center_x, 1.0, TEXT_NORMAL, layout, g);
+
+        // One line per row: name on the left, value on the right, with a
+        // gap before each group
+        let conflict_color: Color = [1.0, 0.2, 0.2, 1.0];
+        let mut y = 2.5;
+        for (index, row) in self.rows.iter().enumerate() {
+            if matches!(row, ControlsRow::Device(_) | ControlsRow::Deadzone) {
+                y += 0.75;
+            }
+            let is_selected = index == self.selected;
+            if is_selected {
+                draw_text([1.0, 1.0, 0.0, 1.0], ">", 2.0, y, TEXT_SMALL, layout, g); // Yellow arrow
+            }
+
+            let color: Color = if self.row_conflict(*row) {
+                conflict_color
+            } else if is_selected {
+                [0.0, 1.0, 1.0, 1.0]
+            } else {
+                [0.7, 0.7, 0.7, 1.0]
+            };
+            let (name, value) = match *row {
+                ControlsRow::Device(player) => (format!("P{} DEVICE", player + 1), self.settings.devices[player].get_name()),
+                ControlsRow::Binding(player, action) => {
+                    let value = if is_selected && self.capturing {
+                        "PRESS A KEY".to_string()
+                    } else {
+                        key_name(self.settings.controls.key(player, action))
+                    };
+                    (format!("P{} {}", player + 1, action.get_name()), value)
+                }
+                ControlsRow::Deadzone => ("STICK DEADZONE".to_string(), format!("{:.1}", self.settings.deadzone)),
+                ControlsRow::BoardWidth => ("BOARD WIDTH".to_string(), self.settings.board_width.to_string()),
+                ControlsRow::BoardHeight => ("BOARD HEIGHT".to_string(), self.settings.board_height.to_string()),
+            };
+            draw_text(color, &name, 3.0, y, TEXT_SMALL, layout, g);
+            draw_text(color, &value, 15.0, y, TEXT_SMALL, layout, g);
+            y += 1.15;
+        }
+
+        if self.warn_conflicts {
+            draw_text_centered(conflict_color, "FIX THE LINES IN RED FIRST", center_x, (height - 5) as f64, TEXT_SMALL, layout, g);
+        }
+
+        let inst_color: Color = [0.5, 0.5, 0.5, 1.0];
+        let instructions = if self.capturing {
+            "PRESS A KEY  BACKSPACE: CANCEL"
+        } else {
+            "ENTER: CHANGE  DELETE: DEFAULTS"
+        };
+        draw_text_centered(inst_color, instructions, center_x, (height - 3) as f64, TEXT_SMALL, layout, g);
+        if !self.capturing {
+            draw_text_centered(inst_color, "BACKSPACE: SAVE AND BACK", center_x, (height - 2) as f64, TEXT_SMALL, layout, g);
+        }
+    }
+}
@@ -0,0 +1,220 @@
+use crate::powerup::{PowerUpKind, ALL_POWER_UPS};
+
+#[derive(Copy, Clone, PartialEq)]
+pub enum GameMode {
+    Easy,
+    Medium,
+    Hard,
+    Timer,      // Play for 60 seconds
+    Survival,   // Endless mode with high score tracking
+}
+
+impl GameMode {
+    pub fn get_base_speed(&self) -> f64 {
+        match self {
+            GameMode::Easy => 0.4,       // Slower start
+            GameMode::Medium => 0.3,    // Medium start
+            GameMode::Hard => 0.2,      // Faster start
+            GameMode::Timer => 0.3,     // Medium speed for timer mode
+            GameMode::Survival => 0.3,   // Medium speed for survival mode
+        }
+    }
+    
+    pub fn get_speed_multiplier(&self) -> f64 {
+        match self {
+            GameMode::Easy => 0.05,      // Slower acceleration
+            GameMode::Medium => 0.08,   // Medium acceleration
+            GameMode::Hard => 0.12,      // Faster acceleration
+            GameMode::Timer => 0.08,     // Medium acceleration
+            GameMode::Survival => 0.08,  // Medium acceleration
+        }
+    }
+    
+    pub fn get_name(&self) -> &str {
+        match self {
+            GameMode::Easy => "EASY",
+            GameMode::Medium => "MEDIUM",
+            GameMode::Hard => "HARD",
+            GameMode::Timer => "TIMER",
+            GameMode::Survival => "SURVIVAL",
+        }
+    }
+    
+    pub fn from_name(name: &str) -> Option<GameMode> {
+        match name {
+            "EASY" => Some(GameMode::Easy),
+            "MEDIUM" => Some(GameMode::Medium),
+            "HARD" => Some(GameMode::Hard),
+            "TIMER" => Some(GameMode::Timer),
+            "SURVIVAL" => Some(GameMode::Survival),
+            _ => None,
+        }
+    }
+    
+    pub fn is_timed_mode(&self) -> bool {
+        matches!(self, GameMode::Timer)
+    }
+    
+    pub fn get_time_limit(&self) -> f64 {
+        match self {
+            GameMode::Timer => 60.0,  // 60 seconds
+            _ => 0.0,
+        }
+    }
+
+    /// Power-ups that can turn up on the board in this mode.
+    pub fn power_ups(&self) -> &'static [PowerUpKind] {
+        match self {
+            GameMode::Easy | GameMode::Medium => &ALL_POWER_UPS,
+            // No safety nets in Hard, just points and reach
+            GameMode::Hard => &[PowerUpKind::Multiplier, PowerUpKind::Ghost, PowerUpKind::Magnet],
+            // Timer is a race for points
+            GameMode::Timer => &[PowerUpKind::Multiplier, PowerUpKind::SlowMotion, PowerUpKind::Magnet],
+            // Survival is about lasting, not scoring
+            GameMode::Survival => &[PowerUpKind::Invincible, PowerUpKind::Ghost, PowerUpKind::SlowMotion],
+        }
+    }
+}
+
+/// Who is playing: one snake alone, or two snakes against or alongside
+/// each other.
+#[derive(Copy, Clone, PartialEq)]
+pub enum PlayerMode {
+    Solo,
+    Versus,
+    CoOp,
+}
+
+impl PlayerMode {
+    pub fn player_count(&self) -> usize {
+        match self {
+            PlayerMode::Solo => 1,
+            PlayerMode::Versus | PlayerMode::CoOp => 2,
+        }
+    }
+
+    /// Co-op players pool their points into one team score.
+    pub fn shares_score(&self) -> bool {
+        matches!(self, PlayerMode::CoOp)
+    }
+
+    pub fn get_name(&self) -> &str {
+        match self {
+            PlayerMode::Solo => "1P",
+            PlayerMode::Versus => "2P VERSUS",
+            PlayerMode::CoOp => "2P CO-OP",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<PlayerMode> {
+        match name {
+            "1P" => Some(PlayerMode::Solo),
+            "2P VERSUS" => Some(PlayerMode::Versus),
+            "2P CO-OP" => Some(PlayerMode::CoOp),
+            _ => None,
+        }
+    }
+}
+
+/// What happens to a snake's body once it dies.
+#[derive(Copy, Clone, PartialEq)]
+pub enum CorpseRule {
+    Remove,   // The body disappears
+    Obstacle, // The body stays on the board as a wall
+}
+
+impl CorpseRule {
+    pub fn get_name(&self) -> &str {
+        match self {
+            CorpseRule::Remove => "REMOVE",
+            CorpseRule::Obstacle => "OBSTACLE",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<CorpseRule> {
+        match name {
+            "REMOVE" => Some(CorpseRule::Remove),
+            "OBSTACLE" => Some(CorpseRule::Obstacle),
+            _ => None,
+        }
+    }
+}
+
+/// What happens in versus once only one snake is left.
+#[derive(Copy, Clone, PartialEq)]
+pub enum SurvivorRule {
+    WinsNow,  // The match ends and the survivor wins
+    PlaysOn,  // The survivor keeps scoring until it dies too
+}
+
+impl SurvivorRule {
+    pub fn get_name(&self) -> &str {
+        match self {
+            SurvivorRule::WinsNow => "WINS NOW",
+            SurvivorRule::PlaysOn => "PLAYS ON",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<SurvivorRule> {
+        match name {
+            "WINS NOW" => Some(SurvivorRule::WinsNow),
+            "PLAYS ON" => Some(SurvivorRule::PlaysOn),
+            _ => None,
+        }
+    }
+}
+
+/// What clears a campaign map.
+#[derive(Copy, Clone, PartialEq)]
+pub enum Goal {
+    Eat(u32),     // Apples
+    Survive(u32), // Seconds
+    Length(usize), // Blocks, head included
+}
+
+impl Goal {
+    /// Name and target as used in replays, e.g. `EAT 10`.
+    pub fn get_name(&self) -> String {
+        match self {
+            Goal::Eat(apples) => format!("EAT {}", apples),
+            Goal::Survive(seconds) => format!("SURVIVE {}", seconds),
+            Goal::Length(length) => format!("LENGTH {}", length),
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Goal> {
+        let (kind, target) = name.split_once(' ')?;
+        match kind {
+            "EAT" => target.parse().ok().map(Goal::Eat),
+            "SURVIVE" => target.parse().ok().map(Goal::Survive),
+            "LENGTH" => target.parse().ok().map(Goal::Length),
+            _ => None,
+        }
+    }
+
+    /// The goal spelled out for the level select screen.
+    pub fn describe(&self) -> String {
+        match self {
+            Goal::Eat(apples) => format!("EAT {} APPLES", apples),
+            Goal::Survive(seconds) => format!("SURVIVE {} SECONDS", seconds),
+            Goal::Length(length) => format!("GROW TO LENGTH {}", length),
+        }
+    }
+
+    /// Just the kind of goal, short enough for the side panel.
+    pub fn short_name(&self) -> &str {
+        match self {
+            Goal::Eat(_) => "EAT",
+            Goal::Survive(_) => "SURVIVE",
+            Goal::Length(_) => "LENGTH",
+        }
+    }
+
+    pub fn target(&self) -> u64 {
+        match *self {
+            Goal::Eat(apples) => u64::from(apples),
+            Goal::Survive(seconds) => u64::from(seconds),
+            Goal::Length(length) => length as u64,
+        }
+    }
+}
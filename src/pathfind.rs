@@ -0,0 +1,419 @@
+//! Shortest paths over the board grid, for enemies and snake bots.
+//!
+//! A `Grid` records which cells are blocked and, optionally, when they come
+//! free: a snake's tail moves on as it goes, so the block `n` from its end
+//! is out of the way after `n + 1` moves. Searches count each move as one
+//! step and only enter a cell once it is free at the step they reach it.
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, VecDeque};
+
+use crate::level::Level;
+use crate::sim::Simulation;
+use crate::snake::{wrap_position, Direction, Snake};
+
+const DIRECTIONS: [Direction; 4] = [Direction::Up, Direction::Down, Direction::Left, Direction::Right];
+
+// `free_at` value for a cell that never comes free
+const ALWAYS: u32 = u32::MAX;
+
+/// Which cells of a board can be entered, and from which step on.
+#[derive(Clone)]
+pub struct Grid {
+    width: i32,
+    height: i32,
+    wrap: bool,
+    free_at: Vec<u32>, // One per cell: the first step it can be entered on
+}
+
+impl Grid {
+    /// An empty `width` x `height` board. The outer ring is the border; with
+    /// `wrap` moves across it come in at the far side, otherwise it blocks.
+    pub fn new(width: i32, height: i32, wrap: bool) -> Grid {
+        let mut grid = Grid { width, height, wrap, free_at: vec![0; (width * height) as usize] };
+        for y in 0..height {
+            for x in 0..width {
+                if x == 0 || y == 0 || x == width - 1 || y == height - 1 {
+                    grid.block(x, y);
+                }
+            }
+        }
+        grid
+    }
+
+    /// The border and inner walls of `level`.
+    pub fn from_level(level: &Level, wrap: bool) -> Grid {
+        let mut grid = Grid::new(level.width(), level.height(), wrap);
+        for (x, y) in level.walls() {
+            grid.block(x, y);
+        }
+        grid
+    }
+
+    /// Everything in the way on `sim`'s board right now: walls, corpses,
+    /// enemies and living snakes. With `time_aware`, snake bodies free up
+    /// from the tail as the search goes on; otherwise they stay put.
+    pub fn from_sim(sim: &Simulation, time_aware: bool) -> Grid {
+        let mut grid = Grid::from_level(sim.map(), sim.config().wrap_walls);
+        for &(x, y) in sim.obstacles() {
+            grid.block(x, y);
+        }
+        for enemy in sim.enemies() {
+            let (x, y) = enemy.position();
+            grid.block(x, y);
+        }
+        for (player, snake) in sim.snakes().iter().enumerate() {
+            if sim.is_alive(player) {
+                grid.block_snake(snake, time_aware);
+            }
+        }
+        grid
+    }
+
+    fn index(&self, x: i32, y: i32) -> Option<usize> {
+        (x >= 0 && y >= 0 && x < self.width && y < self.height).then(|| (y * self.width + x) as usize)
+    }
+
+    /// Blocks (x, y) for good.
+    pub fn block(&mut self, x: i32, y: i32) {
+        self.block_until(x, y, ALWAYS);
+    }
+
+    /// Blocks (x, y) until `step` moves have been made. A cell already
+    /// blocked for longer stays so.
+    pub fn block_until(&mut self, x: i32, y: i32, step: u32) {
+        if let Some(index) = self.index(x, y) {
+            self.free_at[index] = self.free_at[index].max(step);
+        }
+    }
+
+    /// Blocks every block of `snake`. With `time_aware` each block frees
+    /// once the snake has moved past it, the last one after a single move;
+    /// otherwise the whole body blocks for good.
+    pub fn block_snake(&mut self, snake: &Snake, time_aware: bool) {
+        let len = snake.len() as u32;
+        for (i, (x, y)) in snake.body().enumerate() {
+            self.block_until(x, y, if time_aware { len - i as u32 } else { ALWAYS });
+        }
+    }
+
+    /// Whether (x, y) can be entered by the move that makes it `step`.
+    pub fn is_open(&self, x: i32, y: i32, step: u32) -> bool {
+        self.index(x, y).is_some_and(|index| self.free_at[index] <= step)
+    }
+
+    /// The cell one move from `cell`, wrapped back onto the board if the
+    /// border wraps.
+    pub fn neighbour(&self, cell: (i32, i32), direction: Direction) -> (i32, i32) {
+        let (dx, dy) = direction.offset();
+        let (x, y) = (cell.0 + dx, cell.1 + dy);
+        if self.wrap { wrap_position(x, y, self.width, self.height) } else { (x, y) }
+    }
+
+    /// The direction leading from `from` to the neighbouring cell `to`.
+    pub fn direction_to(&self, from: (i32, i32), to: (i32, i32)) -> Option<Direction> {
+        DIRECTIONS.into_iter().find(|&direction| self.neighbour(from, direction) == to)
+    }
+
+    /// Fewest moves from `from` to `to` on an empty board, across the
+    /// border too if it wraps.
+    pub fn distance(&self, from: (i32, i32), to: (i32, i32)) -> u32 {
+        let axis = |a: i32, b: i32, size: i32| {
+            let straight = (a - b).unsigned_abs();
+            // Inside the border there are `size - 2` cells to go round
+            if self.wrap { straight.min((size - 2) as u32 - straight) } else { straight }
+        };
+        axis(from.0, to.0, self.width) + axis(from.1, to.1, self.height)
+    }
+
+    /// Breadth-first search from `from` to the nearest cell `is_goal`
+    /// accepts. Returns the cells moved through, ending at the goal and
+    /// not including `from`, or None if no goal can be reached.
+    pub fn bfs(&self, from: (i32, i32), is_goal: impl Fn((i32, i32)) -> bool) -> Option<Vec<(i32, i32)>> {
+        let start = self.index(from.0, from.1)?;
+        let mut came_from: Vec<Option<usize>> = vec![None; self.free_at.len()];
+        let mut seen = vec![false; self.free_at.len()];
+        seen[start] = true;
+        let mut queue = VecDeque::from([(from, 0)]);
+        while let Some((cell, step)) = queue.pop_front() {
+            if cell != from && is_goal(cell) {
+                return Some(self.trace(&came_from, start, cell));
+            }
+            for direction in DIRECTIONS {
+                let (x, y) = self.neighbour(cell, direction);
+                if self.is_open(x, y, step + 1) {
+                    let index = self.index(x, y).unwrap();
+                    if !seen[index] {
+                        seen[index] = true;
+                        came_from[index] = self.index(cell.0, cell.1);
+                        queue.push_back(((x, y), step + 1));
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /// A* search from `from` to `to`, guided by the distance left. Returns
+    /// the cells moved through, ending at `to` and not including `from`,
+    /// or None if `to` can't be reached.
+    pub fn a_star(&self, from: (i32, i32), to: (i32, i32)) -> Option<Vec<(i32, i32)>> {
+        let start = self.index(from.0, from.1)?;
+        let mut came_from: Vec<Option<usize>> = vec![None; self.free_at.len()];
+        let mut best = vec![u32::MAX; self.free_at.len()];
+        let mut closed = vec![false; self.free_at.len()];
+        best[start] = 0;
+        // Ordered by estimated total length, then by fewest moves so far
+        let mut open = BinaryHeap::from([Reverse((self.distance(from, to), 0, from))]);
+        while let Some(Reverse((_, step, cell))) = open.pop() {
+            let index = self.index(cell.0, cell.1).unwrap();
+            if closed[index] {
+                continue;
+            }
+            closed[index] = true;
+            if cell == to && cell != from {
+                return Some(self.trace(&came_from, start, cell));
+            }
+            for direction in DIRECTIONS {
+                let (x, y) = self.neighbour(cell, direction);
+                if !self.is_open(x, y, step + 1) {
+                    continue;
+                }
+                let next = self.index(x, y).unwrap();
+                if step + 1 < best[next] {
+                    best[next] = step + 1;
+                    came_from[next] = Some(index);
+                    open.push(Reverse((step + 1 + self.distance((x, y), to), step + 1, (x, y))));
+                }
+            }
+        }
+        None
+    }
+
+    /// How many cells can be reached from `from`, itself included, by a
+    /// search that stands there at `step`. A snake needs at least its own
+    /// length of room to be sure of not trapping itself.
+    pub fn flood_fill(&self, from: (i32, i32), step: u32) -> usize {
+        let Some(start) = self.index(from.0, from.1) else {
+            return 0;
+        };
+        let mut seen = vec![false; self.free_at.len()];
+        seen[start] = true;
+        let mut count = 1;
+        let mut queue = VecDeque::from([(from, step)]);
+        while let Some((cell, step)) = queue.pop_front() {
+            for direction in DIRECTIONS {
+                let (x, y) = self.neighbour(cell, direction);
+                if self.is_open(x, y, step + 1) {
+                    let index = self.index(x, y).unwrap();
+                    if !seen[index] {
+                        seen[index] = true;
+                        count += 1;
+                        queue.push_back(((x, y), step + 1));
+                    }
+                }
+            }
+        }
+        count
+    }
+
+    /// The first move of a shortest path from `from` to the nearest cell
+    /// `is_goal` accepts.
+    pub fn first_step(&self, from: (i32, i32), is_goal: impl Fn((i32, i32)) -> bool) -> Option<Direction> {
+        let path = self.bfs(from, is_goal)?;
+        self.direction_to(from, path[0])
+    }
+
+    // Walks `came_from` back from `end` to `start`, returning the path
+    // after `start` in order
+    fn trace(&self, came_from: &[Option<usize>], start: usize, end: (i32, i32)) -> Vec<(i32, i32)> {
+        let mut path = vec![end];
+        let mut index = self.index(end.0, end.1).unwrap();
+        while let Some(previous) = came_from[index] {
+            if previous == start {
+                break;
+            }
+            path.push((previous as i32 % self.width, previous as i32 / self.width));
+            index = previous;
+        }
+        path.reverse();
+        path
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A grid from rows of text: `#` blocks for good, a digit blocks until
+    // that many moves have been made, anything else is open. `S` and `G`
+    // mark the start and goal.
+    fn board(rows: &[&str], wrap: bool) -> (Grid, (i32, i32), (i32, i32)) {
+        let mut grid = Grid::new(rows[0].len() as i32, rows.len() as i32, wrap);
+        let (mut start, mut goal) = ((0, 0), (0, 0));
+        for (y, row) in rows.iter().enumerate() {
+            for (x, cell) in row.chars().enumerate() {
+                let (x, y) = (x as i32, y as i32);
+                match cell {
+                    '#' => grid.block(x, y),
+                    'S' => start = (x, y),
+                    'G' => goal = (x, y),
+                    digit if digit.is_ascii_digit() => grid.block_until(x, y, digit.to_digit(10).unwrap()),
+                    _ => {}
+                }
+            }
+        }
+        (grid, start, goal)
+    }
+
+    #[test]
+    fn straight_line_on_an_open_board() {
+        let (grid, start, goal) = board(&[
+            "#######",
+            "#S....#",
+            "#.....#",
+            "#....G#",
+            "#######",
+        ], false);
+        let path = grid.bfs(start, |cell| cell == goal).unwrap();
+        assert_eq!(path.len(), 6);
+        assert_eq!(path.last(), Some(&goal));
+        assert_eq!(grid.a_star(start, goal).unwrap().len(), 6);
+    }
+
+    #[test]
+    fn goes_round_walls() {
+        let (grid, start, goal) = board(&[
+            "#######",
+            "#S.#G.#",
+            "#..#..#",
+            "#.....#",
+            "#######",
+        ], false);
+        let path = grid.a_star(start, goal).unwrap();
+        assert_eq!(path.len(), 7);
+        assert!(path.iter().all(|&(x, y)| grid.is_open(x, y, 0)));
+        assert_eq!(grid.bfs(start, |cell| cell == goal).unwrap().len(), 7);
+    }
+
+    #[test]
+    fn path_steps_are_neighbours() {
+        let (grid, start, goal) = board(&[
+            "########",
+            "#S#....#",
+            "#.#.##.#",
+            "#...#G.#",
+            "########",
+        ], false);
+        let path = grid.a_star(start, goal).unwrap();
+        let mut cell = start;
+        for &next in &path {
+            assert!(grid.direction_to(cell, next).is_some());
+            cell = next;
+        }
+        assert_eq!(cell, goal);
+    }
+
+    #[test]
+    fn walled_off_goal_is_unreachable() {
+        let (grid, start, goal) = board(&[
+            "#######",
+            "#S.#..#",
+            "#..#G.#",
+            "#..#..#",
+            "#######",
+        ], false);
+        assert!(grid.bfs(start, |cell| cell == goal).is_none());
+        assert!(grid.a_star(start, goal).is_none());
+        assert!(grid.first_step(start, |cell| cell == goal).is_none());
+    }
+
+    #[test]
+    fn wrapping_border_gives_a_shortcut() {
+        let rows = [
+            "#######",
+            "#S...G#",
+            "#######",
+        ];
+        let (walled, start, goal) = board(&rows, false);
+        assert_eq!(walled.a_star(start, goal).unwrap().len(), 4);
+
+        let (wrapping, start, goal) = board(&rows, true);
+        assert_eq!(wrapping.distance(start, goal), 1);
+        assert_eq!(wrapping.a_star(start, goal), Some(vec![goal]));
+        assert!(wrapping.first_step(start, |cell| cell == goal) == Some(Direction::Left));
+    }
+
+    #[test]
+    fn tail_frees_up_in_time() {
+        // The corridor is plugged by a body block that moves away after two
+        // moves, exactly when the path gets there
+        let (grid, start, goal) = board(&[
+            "#######",
+            "#S.2G.#",
+            "#######",
+        ], false);
+        assert_eq!(grid.bfs(start, |cell| cell == goal).unwrap().len(), 3);
+
+        // One that stays three moves is still there
+        let (grid, start, goal) = board(&[
+            "#######",
+            "#S.3G.#",
+            "#######",
+        ], false);
+        assert!(grid.bfs(start, |cell| cell == goal).is_none());
+        assert!(grid.a_star(start, goal).is_none());
+    }
+
+    #[test]
+    fn snake_bodies_free_from_the_tail() {
+        let mut grid = Grid::new(10, 5, false);
+        // Head at (5, 2), body trailing left to (3, 2)
+        let snake = Snake::facing(5, 2, Direction::Right);
+        grid.block_snake(&snake, true);
+        assert!(!grid.is_open(5, 2, 2));
+        assert!(grid.is_open(5, 2, 3));
+        assert!(!grid.is_open(3, 2, 0));
+        assert!(grid.is_open(3, 2, 1));
+
+        let mut solid = Grid::new(10, 5, false);
+        solid.block_snake(&snake, false);
+        assert!(!solid.is_open(3, 2, 100));
+    }
+
+    #[test]
+    fn flood_fill_counts_the_pocket() {
+        let (grid, start, goal) = board(&[
+            "#######",
+            "#S.#G.#",
+            "#..#..#",
+            "#######",
+        ], false);
+        assert_eq!(grid.flood_fill(start, 0), 4);
+        assert_eq!(grid.flood_fill(goal, 0), 4);
+
+        // A gap that opens in time lets the fill through, one that opens
+        // too late doesn't
+        let (grid, start, _) = board(&[
+            "#######",
+            "#S.3..#",
+            "#######",
+        ], false);
+        assert_eq!(grid.flood_fill(start, 0), 2);
+        assert_eq!(grid.flood_fill(start, 1), 5);
+    }
+
+    #[test]
+    fn nearest_of_several_goals() {
+        let (grid, start, _) = board(&[
+            "#########",
+            "#..S....#",
+            "#.......#",
+            "#########",
+        ], false);
+        let goals = [(1, 2), (7, 1)];
+        let path = grid.bfs(start, |cell| goals.contains(&cell)).unwrap();
+        assert_eq!(path.last(), Some(&(1, 2)));
+        assert_eq!(path.len(), 3);
+    }
+}
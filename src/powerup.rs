@@ -0,0 +1,139 @@
+//! Power-ups: items that turn up on the board now and then and give the
+//! snake that picks one up a timed ability.
+//!
+//! Different power-ups run side by side. Picking up one that is already
+//! running adds its duration again, up to `MAX_STACK` durations, and each
+//! extra multiplier also raises the score multiplier by one, up to
+//! `MAX_MULTIPLIER`.
+
+use crate::sim::TICK_RATE;
+
+#[derive(Copy, Clone, PartialEq)]
+pub enum PowerUpKind {
+    Multiplier, // Food scores more
+    Invincible, // Pass through enemies, corpses and snakes; only walls still kill
+    Ghost,      // Pass through your own body
+    SlowMotion, // Everyone else, enemies included, moves slower
+    Magnet,     // Nearby food drifts towards your head
+}
+
+pub const ALL_POWER_UPS: [PowerUpKind; 5] = [
+    PowerUpKind::Multiplier,
+    PowerUpKind::Invincible,
+    PowerUpKind::Ghost,
+    PowerUpKind::SlowMotion,
+    PowerUpKind::Magnet,
+];
+
+/// Most durations of one power-up a player can bank.
+pub const MAX_STACK: u32 = 2;
+pub const MAX_MULTIPLIER: i32 = 4;
+
+// Ticks an uncollected power-up stays on the board
+const ITEM_TICKS: u32 = 8 * TICK_RATE;
+// Items blink for their last two seconds
+const BLINK_TICKS: u32 = 2 * TICK_RATE;
+
+impl PowerUpKind {
+    /// One letter to mark the item and its HUD icon.
+    pub fn letter(&self) -> &str {
+        match self {
+            PowerUpKind::Multiplier => "X",
+            PowerUpKind::Invincible => "I",
+            PowerUpKind::Ghost => "G",
+            PowerUpKind::SlowMotion => "S",
+            PowerUpKind::Magnet => "M",
+        }
+    }
+
+    /// Ticks one pickup lasts.
+    pub fn duration(&self) -> u32 {
+        let seconds = match self {
+            PowerUpKind::Multiplier => 15,
+            PowerUpKind::Invincible => 5,
+            PowerUpKind::Ghost => 8,
+            PowerUpKind::SlowMotion => 6,
+            PowerUpKind::Magnet => 10,
+        };
+        seconds * TICK_RATE
+    }
+
+    fn index(&self) -> usize {
+        ALL_POWER_UPS.iter().position(|kind| kind == self).unwrap()
+    }
+}
+
+/// A power-up waiting on the board to be picked up.
+#[derive(Copy, Clone)]
+pub struct PowerUp {
+    pub x: i32,
+    pub y: i32,
+    pub kind: PowerUpKind,
+    pub ticks_left: u32,
+}
+
+impl PowerUp {
+    pub fn new(x: i32, y: i32, kind: PowerUpKind) -> PowerUp {
+        PowerUp { x, y, kind, ticks_left: ITEM_TICKS }
+    }
+
+    pub fn position(&self) -> (i32, i32) {
+        (self.x, self.y)
+    }
+
+    /// Whether an item about to disappear is in the off half of its blink.
+    pub fn blinked_out(&self) -> bool {
+        self.ticks_left < BLINK_TICKS && (self.ticks_left / 8) % 2 == 1
+    }
+
+    /// Counts down the item's time on the board. Returns false once it has
+    /// run out.
+    pub fn tick(&mut self) -> bool {
+        self.ticks_left = self.ticks_left.saturating_sub(1);
+        self.ticks_left > 0
+    }
+}
+
+/// The power-ups one player has running.
+#[derive(Copy, Clone)]
+pub struct ActivePowerUps {
+    ticks_left: [u32; ALL_POWER_UPS.len()],
+    multiplier: i32,
+}
+
+impl Default for ActivePowerUps {
+    fn default() -> ActivePowerUps {
+        ActivePowerUps { ticks_left: [0; ALL_POWER_UPS.len()], multiplier: 1 }
+    }
+}
+
+impl ActivePowerUps {
+    pub fn is_active(&self, kind: PowerUpKind) -> bool {
+        self.ticks_left[kind.index()] > 0
+    }
+
+    /// Share of the most `kind` can have banked that is left, from 0 to 1.
+    pub fn fraction_left(&self, kind: PowerUpKind) -> f64 {
+        self.ticks_left[kind.index()] as f64 / (kind.duration() * MAX_STACK) as f64
+    }
+
+    /// What food is worth to this player: 1 unless a multiplier is running.
+    pub fn score_multiplier(&self) -> i32 {
+        if self.is_active(PowerUpKind::Multiplier) { self.multiplier } else { 1 }
+    }
+
+    pub fn add(&mut self, kind: PowerUpKind) {
+        if kind == PowerUpKind::Multiplier {
+            self.multiplier = if self.is_active(kind) { (self.multiplier + 1).min(MAX_MULTIPLIER) } else { 2 };
+        }
+        let ticks = &mut self.ticks_left[kind.index()];
+        *ticks = (*ticks + kind.duration()).min(kind.duration() * MAX_STACK);
+    }
+
+    /// Counts every running power-up down by one tick.
+    pub fn tick(&mut self) {
+        for ticks in &mut self.ticks_left {
+            *ticks = ticks.saturating_sub(1);
+        }
+    }
+}
@@ -0,0 +1,299 @@
+//! Match recordings. The match config, the map and every tick's inputs are
+//! enough to rebuild a match exactly through `Simulation`.
+//!
+//! Files are plain text: a short header, the map as a `level <lines>` line
+//! followed by that many lines of level file, then run-length encoded
+//! inputs, one `<count> <p1><p2>...` line per run with one character per
+//! player: `U`, `D`, `L`, `R` or `.` for no input.
+
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::level::Level;
+use crate::mode::{CorpseRule, GameMode, Goal, PlayerMode, SurvivorRule};
+use crate::sim::{MatchConfig, TickInputs};
+use crate::snake::Direction;
+
+pub const REPLAY_DIR: &str = "replays";
+
+const MAGIC: &str = "snake-replay";
+// Bumped whenever the file layout or the rules change in a way that makes
+// older recordings play out differently.
+// Version 2 added the players line.
+// Version 3 added the corpse and survivor rules.
+// Version 4 moved snakes only on the movement clock.
+// Version 5 added the input buffer.
+// Version 6 added wrapping walls.
+// Version 7 added maps.
+// Version 8 added campaign goals.
+// Version 9 added food kinds, several foods at once.
+// Version 10 replaced the Hard multiplier with power-ups.
+// Version 11 added enemy kinds.
+// Version 12 moved enemies by pathfinding, blocked by snakes.
+// Version 13 stopped two enemies moving into the same cell.
+// Version 14 counted only apples towards eating goals.
+// Version 15 made magnets pull across wrapping walls.
+const VERSION: u32 = 15;
+
+pub struct Replay {
+    pub config: MatchConfig,
+    pub level: Level,
+    pub inputs: Vec<TickInputs>,
+}
+
+impl Replay {
+    pub fn new(config: MatchConfig, level: Level) -> Replay {
+        Replay {
+            config,
+            level,
+            inputs: Vec::new(),
+        }
+    }
+
+    pub fn record(&mut self, inputs: TickInputs) {
+        self.inputs.push(inputs);
+    }
+
+    pub fn to_text(&self) -> String {
+        let config = &self.config;
+        let mut text = format!(
+            "{} {}\nseed {}\nmode {}\nplayers {}\ncorpses {}\nsurvivor {}\nbuffer {}\nwalls {}\nsize {} {}\ngoal {}\n",
+            MAGIC,
+            VERSION,
+            config.seed,
+            config.game_mode.get_name(),
+            config.player_mode.get_name(),
+            config.corpse_rule.get_name(),
+            config.survivor_rule.get_name(),
+            config.input_buffer,
+            if config.wrap_walls { "WRAP" } else { "SOLID" },
+            config.width,
+            config.height,
+            config.goal.map_or("NONE".to_string(), |goal| goal.get_name())
+        );
+        let level = self.level.to_text();
+        text.push_str(&format!("level {}\n{}ticks\n", level.lines().count(), level));
+
+        let players = config.player_mode.player_count();
+        let mut runs: Vec<(usize, String)> = Vec::new();
+        for inputs in &self.inputs {
+            let code: String = inputs.directions[..players].iter().map(|dir| direction_char(*dir)).collect();
+            match runs.last_mut() {
+                Some((count, last)) if *last == code => *count += 1,
+                _ => runs.push((1, code)),
+            }
+        }
+        for (count, code) in runs {
+            text.push_str(&format!("{} {}\n", count, code));
+        }
+        text
+    }
+
+    pub fn parse(text: &str) -> io::Result<Replay> {
+        let mut lines = text.lines();
+        let version: u32 = lines.next()
+            .and_then(|line| line.strip_prefix(MAGIC))
+            .and_then(|rest| rest.trim().parse().ok())
+            .ok_or_else(|| invalid("not a snake replay"))?;
+        if version != VERSION {
+            return Err(invalid("recorded by another version of the game"));
+        }
+
+        let seed = header_value(lines.next(), "seed")?
+            .parse()
+            .map_err(|_| invalid("bad seed"))?;
+        let game_mode = GameMode::from_name(header_value(lines.next(), "mode")?)
+            .ok_or_else(|| invalid("unknown game mode"))?;
+        let player_mode = PlayerMode::from_name(header_value(lines.next(), "players")?)
+            .ok_or_else(|| invalid("unknown player mode"))?;
+        let corpse_rule = CorpseRule::from_name(header_value(lines.next(), "corpses")?)
+            .ok_or_else(|| invalid("unknown corpse rule"))?;
+        let survivor_rule = SurvivorRule::from_name(header_value(lines.next(), "survivor")?)
+            .ok_or_else(|| invalid("unknown survivor rule"))?;
+        let input_buffer = header_value(lines.next(), "buffer")?
+            .parse()
+            .map_err(|_| invalid("bad input buffer"))?;
+        let wrap_walls = match header_value(lines.next(), "walls")? {
+            "WRAP" => true,
+            "SOLID" => false,
+            _ => return Err(invalid("unknown wall type")),
+        };
+        let mut size = header_value(lines.next(), "size")?.split_whitespace();
+        let width = size.next().and_then(|w| w.parse().ok()).ok_or_else(|| invalid("bad width"))?;
+        let height = size.next().and_then(|h| h.parse().ok()).ok_or_else(|| invalid("bad height"))?;
+        let goal = match header_value(lines.next(), "goal")? {
+            "NONE" => None,
+            name => Some(Goal::from_name(name).ok_or_else(|| invalid("unknown goal"))?),
+        };
+        let level_lines: usize = header_value(lines.next(), "level")?
+            .parse()
+            .map_err(|_| invalid("bad level length"))?;
+        let level_text: Vec<&str> = lines.by_ref().take(level_lines).collect();
+        let level = Level::parse(&level_text.join("\n"))?;
+        if lines.next() != Some("ticks") {
+            return Err(invalid("missing tick section"));
+        }
+
+        let mut replay = Replay::new(MatchConfig {
+            width,
+            height,
+            game_mode,
+            player_mode,
+            corpse_rule,
+            survivor_rule,
+            input_buffer,
+            wrap_walls,
+            goal,
+            seed,
+        }, level);
+        for line in lines.filter(|line| !line.is_empty()) {
+            let (count, code) = line.split_once(' ').ok_or_else(|| invalid("bad tick line"))?;
+            let count: usize = count.parse().map_err(|_| invalid("bad tick count"))?;
+            let mut chars = code.chars();
+            let mut inputs = TickInputs::default();
+            for dir in inputs.directions.iter_mut().take(player_mode.player_count()) {
+                *dir = parse_direction(chars.next())?;
+            }
+            replay.inputs.extend(std::iter::repeat_n(inputs, count));
+        }
+        Ok(replay)
+    }
+
+    /// Writes the replay into `REPLAY_DIR` and returns the file it created.
+    /// Never overwrites: a second recording of the same seed in the same
+    /// second gets a numbered name.
+    pub fn save(&self) -> io::Result<PathBuf> {
+        fs::create_dir_all(REPLAY_DIR)?;
+        let stamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let mut copy = 1;
+        loop {
+            let name = match copy {
+                1 => format!("replay-{}-{}.txt", stamp, self.config.seed),
+                _ => format!("replay-{}-{}-{}.txt", stamp, self.config.seed, copy),
+            };
+            let path = Path::new(REPLAY_DIR).join(name);
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(mut file) => {
+                    file.write_all(self.to_text().as_bytes())?;
+                    return Ok(path);
+                }
+                Err(err) if err.kind() == io::ErrorKind::AlreadyExists => copy += 1,
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    pub fn load(path: &Path) -> io::Result<Replay> {
+        Replay::parse(&fs::read_to_string(path)?)
+    }
+
+    /// Replay files in `REPLAY_DIR`, newest first.
+    pub fn list() -> Vec<PathBuf> {
+        let mut files: Vec<PathBuf> = match fs::read_dir(REPLAY_DIR) {
+            Ok(entries) => entries
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .filter(|path| path.extension().is_some_and(|ext| ext == "txt"))
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+        // File names start with a timestamp, so name order is age order
+        files.sort();
+        files.reverse();
+        files
+    }
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+fn header_value<'a>(line: Option<&'a str>, key: &str) -> io::Result<&'a str> {
+    line.and_then(|line| line.strip_prefix(key))
+        .and_then(|rest| rest.strip_prefix(' '))
+        .ok_or_else(|| invalid(&format!("missing {}", key)))
+}
+
+fn direction_char(dir: Option<Direction>) -> char {
+    match dir {
+        Some(Direction::Up) => 'U',
+        Some(Direction::Down) => 'D',
+        Some(Direction::Left) => 'L',
+        Some(Direction::Right) => 'R',
+        None => '.',
+    }
+}
+
+fn parse_direction(c: Option<char>) -> io::Result<Option<Direction>> {
+    match c {
+        Some('U') => Ok(Some(Direction::Up)),
+        Some('D') => Ok(Some(Direction::Down)),
+        Some('L') => Ok(Some(Direction::Left)),
+        Some('R') => Ok(Some(Direction::Right)),
+        Some('.') => Ok(None),
+        _ => Err(invalid("bad input code")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn replay() -> Replay {
+        let config = MatchConfig {
+            width: 30,
+            height: 30,
+            game_mode: GameMode::Hard,
+            player_mode: PlayerMode::Versus,
+            corpse_rule: CorpseRule::Obstacle,
+            survivor_rule: SurvivorRule::PlaysOn,
+            input_buffer: 3,
+            wrap_walls: true,
+            goal: None,
+            seed: 42,
+        };
+        let mut replay = Replay::new(config, Level::open(30, 30));
+        let mut inputs = TickInputs::default();
+        replay.record(inputs);
+        replay.record(inputs);
+        inputs.directions[0] = Some(Direction::Up);
+        inputs.directions[1] = Some(Direction::Left);
+        replay.record(inputs);
+        replay
+    }
+
+    #[test]
+    fn round_trips_through_text() {
+        let original = replay();
+        let text = original.to_text();
+        let parsed = Replay::parse(&text).unwrap();
+        assert_eq!(parsed.to_text(), text);
+        assert_eq!(parsed.config.seed, 42);
+        assert!(parsed.config.game_mode == GameMode::Hard);
+        assert!(parsed.config.player_mode == PlayerMode::Versus);
+        assert!(parsed.config.corpse_rule == CorpseRule::Obstacle);
+        assert!(parsed.config.wrap_walls);
+        assert_eq!(parsed.inputs.len(), 3);
+        assert!(parsed.inputs[2].directions[1] == Some(Direction::Left));
+    }
+
+    #[test]
+    fn rejects_other_files() {
+        let text = replay().to_text().replacen(MAGIC, "snake-level", 1);
+        assert_eq!(Replay::parse(&text).err().map(|err| err.kind()), Some(io::ErrorKind::InvalidData));
+    }
+
+    #[test]
+    fn rejects_other_versions() {
+        let text = replay().to_text();
+        for version in [0, VERSION + 1] {
+            let other = text.replacen(&format!("{} {}", MAGIC, VERSION), &format!("{} {}", MAGIC, version), 1);
+            assert_ne!(other, text);
+            assert_eq!(Replay::parse(&other).err().map(|err| err.kind()), Some(io::ErrorKind::InvalidData));
+        }
+    }
+}
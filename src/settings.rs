@@ -0,0 +1,128 @@
+//! Player preferences kept between runs.
+//!
+//! Stored as one `name value` pair per line, e.g. `p1.up UP`, `deadzone 0.3`
+//! or `board 40 30`. Unknown or malformed lines are ignored and missing ones
+//! keep their defaults.
+
+use std::fs;
+use std::io;
+
+use crate::controls::{key_from_name, key_name, Controls, InputDevice, ALL_ACTIONS, KEYBOARD_PLAYERS};
+use crate::level::{clamp_board_size, DEFAULT_BOARD_SIZE, MAX_BOARD_HEIGHT, MAX_BOARD_WIDTH, MIN_BOARD_SIZE};
+
+pub const SETTINGS_FILE: &str = "settings.txt";
+
+/// Choices for the analog stick deadzone.
+pub const DEADZONES: [f64; 5] = [0.1, 0.2, 0.3, 0.4, 0.5];
+
+// The settings screen steps through board sizes this many blocks at a time
+const BOARD_STEP: i32 = 10;
+
+// The next size up from `size`, going back to the smallest after `max`
+fn next_board_size(size: i32, max: i32) -> i32 {
+    let next = (size / BOARD_STEP + 1) * BOARD_STEP;
+    if next > max { MIN_BOARD_SIZE } else { next }
+}
+
+#[derive(Clone)]
+pub struct Settings {
+    pub controls: Controls,
+    pub devices: [InputDevice; KEYBOARD_PLAYERS],
+    pub deadzone: f64, // Stick travel (0-1) ignored around the centre
+    pub board_width: i32,
+    pub board_height: i32,
+}
+
+impl Default for Settings {
+    fn default() -> Settings {
+        Settings {
+            controls: Controls::default(),
+            devices: [InputDevice::Keyboard; KEYBOARD_PLAYERS],
+            deadzone: 0.3,
+            board_width: DEFAULT_BOARD_SIZE,
+            board_height: DEFAULT_BOARD_SIZE,
+        }
+    }
+}
+
+impl Settings {
+    /// Reads `SETTINGS_FILE`, falling back to defaults for anything missing.
+    pub fn load() -> Settings {
+        let mut settings = Settings::default();
+        let text = fs::read_to_string(SETTINGS_FILE).unwrap_or_default();
+        for (name, value) in text.lines().filter_map(|line| line.split_once(' ')) {
+            settings.apply(name, value.trim());
+        }
+        settings
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let mut text = String::new();
+        for player in 0..KEYBOARD_PLAYERS {
+            text.push_str(&format!("p{}.device {}\n", player + 1, self.devices[player].get_name()));
+            for action in ALL_ACTIONS {
+                text.push_str(&format!(
+                    "p{}.{} {}\n",
+                    player + 1,
+                    action.get_name().to_lowercase(),
+                    key_name(self.controls.key(player, action))
+                ));
+            }
+        }
+        text.push_str(&format!("deadzone {}\n", self.deadzone));
+        text.push_str(&format!("board {} {}\n", self.board_width, self.board_height));
+        fs::write(SETTINGS_FILE, text)
+    }
+
+    /// The player a gamepad is assigned to, if any.
+    pub fn gamepad_player(&self, id: u32) -> Option<usize> {
+        self.devices.iter().position(|device| *device == InputDevice::Gamepad(id))
+    }
+
+    pub fn cycle_deadzone(&mut self) {
+        let next = DEADZONES.iter().position(|d| *d > self.deadzone).unwrap_or(0);
+        self.deadzone = DEADZONES[next];
+    }
+
+    pub fn cycle_board_width(&mut self) {
+        self.board_width = next_board_size(self.board_width, MAX_BOARD_WIDTH);
+    }
+
+    pub fn cycle_board_height(&mut self) {
+        self.board_height = next_board_size(self.board_height, MAX_BOARD_HEIGHT);
+    }
+
+    fn apply(&mut self, name: &str, value: &str) {
+        if name == "deadzone" {
+            if let Ok(deadzone) = value.parse::<f64>() {
+                self.deadzone = deadzone.clamp(0.0, 0.9);
+            }
+            return;
+        }
+        if name == "board" {
+            if let Some((width, height)) = value.split_once(' ')
+                && let (Ok(width), Ok(height)) = (width.trim().parse(), height.trim().parse())
+            {
+                (self.board_width, self.board_height) = clamp_board_size(width, height);
+            }
+            return;
+        }
+
+        // Per-player lines: p<player>.<setting>
+        let Some((player, setting)) = name.split_once('.') else { return };
+        let Some(player) = player.strip_prefix('p').and_then(|p| p.parse::<usize>().ok()) else { return };
+        if !(1..=KEYBOARD_PLAYERS).contains(&player) {
+            return;
+        }
+
+        if setting == "device" {
+            if let Some(device) = InputDevice::from_name(value) {
+                self.devices[player - 1] = device;
+            }
+        } else if let Some(action) = ALL_ACTIONS.iter().find(|a| a.get_name().eq_ignore_ascii_case(setting))
+            && let Some(key) = key_from_name(value)
+        {
+            self.controls.bind(player - 1, *action, key);
+        }
+    }
+}
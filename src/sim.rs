@@ -0,0 +1,1054 @@
+//! Headless game rules. Nothing in here knows about windows or drawing, so a
+//! match can be driven from tests, bots or a server by calling `step`.
+
+use std::collections::VecDeque;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::snake::{wrap_position, Direction, Snake};
+use crate::mode::{CorpseRule, GameMode, Goal, PlayerMode, SurvivorRule};
+use crate::enemy::{Enemy, EnemyKind, Surroundings, ALL_ENEMY_KINDS};
+use crate::level::Level;
+use crate::pathfind::Grid;
+use crate::food::{Food, FoodKind, TEMPO_SECONDS};
+use crate::powerup::{ActivePowerUps, PowerUp, PowerUpKind};
+
+const FOOD_PER_LEVEL: i32 = 5; // Number of foods needed to level up
+
+/// Most snakes a single match can hold.
+pub const MAX_PLAYERS: usize = 4;
+
+/// Allowed range for `MatchConfig::input_buffer`.
+pub const MIN_INPUT_BUFFER: usize = 1;
+pub const MAX_INPUT_BUFFER: usize = 3;
+
+/// Logical ticks per second. All gameplay timing is counted in ticks so a
+/// match plays the same at any frame rate.
+pub const TICK_RATE: u32 = 60;
+pub const TICK_SECONDS: f64 = 1.0 / TICK_RATE as f64;
+
+const ENEMY_MOVE_TICKS: u32 = 18; // Enemies move every 0.3 seconds
+const EXTRA_FOOD_TICKS: u32 = 4 * TICK_RATE; // A chance at another food every 4 seconds
+const MAX_FOODS: usize = 3; // Foods on the board at once
+const POWER_UP_TICKS: u32 = 10 * TICK_RATE; // A power-up turns up every 10 seconds
+const SLOW_MOTION_FACTOR: f64 = 1.5; // Move time of everyone slowed by slow-motion
+const MAGNET_RANGE: i32 = 6; // Cells, counted along the grid, a magnet reaches
+
+// Converts a duration in seconds into a whole number of ticks (at least one)
+fn to_ticks(seconds: f64) -> u32 {
+    ((seconds * TICK_RATE as f64).round() as u32).max(1)
+}
+
+/// Everything needed to set up a match. Together with the per-tick inputs
+/// this fully determines how the match plays out.
+#[derive(Clone, Copy)]
+pub struct MatchConfig {
+    pub width: i32,
+    pub height: i32,
+    pub game_mode: GameMode,
+    pub player_mode: PlayerMode,
+    pub corpse_rule: CorpseRule,
+    pub survivor_rule: SurvivorRule, // Versus only
+    pub input_buffer: usize, // Turns each player can queue up ahead of their snake
+    pub wrap_walls: bool, // Edges lead to the opposite side instead of killing
+    pub goal: Option<Goal>, // Ends the match as a win once met (campaign maps)
+    pub seed: u64,
+}
+
+/// Direction changes the players made since the previous tick, indexed by
+/// player. Entries past the match's player count are ignored.
+#[derive(Clone, Copy, Default)]
+pub struct TickInputs {
+    pub directions: [Option<Direction>; MAX_PLAYERS],
+}
+
+/// What happened during a step, for frontends that want to react to it.
+#[derive(Clone, Copy, Default)]
+pub struct TickEvents {
+    pub snakes_moved: bool,
+    pub ate: [bool; MAX_PLAYERS],
+    pub died: [bool; MAX_PLAYERS],
+    pub level_up: bool,
+    pub game_over: bool,
+}
+
+/// How a finished versus match was decided. Solo and co-op matches have
+/// no winner.
+#[derive(Clone, Copy, PartialEq)]
+pub enum MatchResult {
+    Winner(usize), // Player index
+    Draw,
+}
+
+/// What a snake crashed into.
+#[derive(Clone, Copy, PartialEq)]
+pub enum DeathCause {
+    Wall, // The border or an inner wall
+    Corpse,
+    Enemy,
+    OwnBody,
+    OtherSnake,
+    HeadOn, // Two heads meeting or swapping places
+}
+
+pub const ALL_DEATH_CAUSES: [DeathCause; 6] = [
+    DeathCause::Wall,
+    DeathCause::Corpse,
+    DeathCause::Enemy,
+    DeathCause::OwnBody,
+    DeathCause::OtherSnake,
+    DeathCause::HeadOn,
+];
+
+impl DeathCause {
+    pub fn get_name(&self) -> &str {
+        match self {
+            DeathCause::Wall => "WALL",
+            DeathCause::Corpse => "CORPSE",
+            DeathCause::Enemy => "ENEMY",
+            DeathCause::OwnBody => "OWN BODY",
+            DeathCause::OtherSnake => "OTHER SNAKE",
+            DeathCause::HeadOn => "HEAD-ON",
+        }
+    }
+}
+
+fn spawn_snakes(config: &MatchConfig, level: &Level, count: usize) -> Vec<Snake> {
+    level.spawns[..count]
+        .iter()
+        .map(|spawn| {
+            let mut snake = Snake::facing(spawn.x, spawn.y, spawn.direction);
+            if config.wrap_walls {
+                snake.set_wrap(config.width, config.height);
+            }
+            snake
+        })
+        .collect()
+}
+
+pub struct Simulation {
+    config: MatchConfig,
+    map: Level,
+    snakes: Vec<Snake>,
+    alive: Vec<bool>, // One per player
+    death_causes: Vec<Option<DeathCause>>, // One per player, set when they die
+    turns: Vec<VecDeque<Direction>>, // Queued turns per player, one taken per move
+    obstacles: Vec<(i32, i32)>, // Bodies left behind under `CorpseRule::Obstacle`
+
+    foods: Vec<Food>, // Always at least one apple once the match starts
+    food_ticks: u32, // Ticks since the last extra food
+
+    game_over: bool,
+    result: Option<MatchResult>,
+    goal_reached: bool,
+    move_ticks: Vec<u32>, // Ticks since each snake last moved
+    tempo: Vec<(f64, u32)>, // Move time factor from speed or slow fruit, and ticks it lasts
+
+    scores: Vec<i32>, // One per player
+    level: i32,
+    foods_eaten: i32,
+    apples_eaten: u64, // Counted towards `Goal::Eat`
+
+    final_level: i32,
+
+    // Timer mode
+    tick: u64,
+    time_limit_ticks: u64,
+
+    // Enemies (one from level 1, a new kind each level up to 5)
+    enemies: Vec<Enemy>,
+    enemy_move_ticks: u32,
+
+    // Power-ups on the board and those each player has running
+    power_up_items: Vec<PowerUp>,
+    power_up_ticks: u32, // Ticks since the last power-up appeared
+    power_ups: Vec<ActivePowerUps>,
+
+    // Every random decision is drawn from here so a seed replays exactly
+    rng: StdRng,
+}
+
+impl Simulation {
+    /// A match on `level`, whose size replaces the one in `config`.
+    pub fn new(mut config: MatchConfig, level: Level) -> Simulation {
+        config.width = level.width();
+        config.height = level.height();
+        let game_mode = config.game_mode;
+        let players = config.player_mode.player_count();
+        let time_limit_ticks = (game_mode.get_time_limit() * TICK_RATE as f64).round() as u64;
+        Simulation {
+            config,
+            snakes: spawn_snakes(&config, &level, players),
+            map: level,
+            alive: vec![true; players],
+            death_causes: vec![None; players],
+            turns: vec![VecDeque::new(); players],
+            obstacles: Vec::new(),
+            move_ticks: vec![0; players],
+            tempo: vec![(1.0, 0); players],
+            foods: Vec::new(),
+            food_ticks: 0,
+            game_over: false,
+            result: None,
+            goal_reached: false,
+            scores: vec![0; players],
+            level: 1,
+            foods_eaten: 0,
+            apples_eaten: 0,
+            final_level: 1,
+            tick: 0,
+            time_limit_ticks,
+            enemies: Vec::new(),
+            enemy_move_ticks: 0,
+            power_up_items: Vec::new(),
+            power_up_ticks: 0,
+            power_ups: vec![ActivePowerUps::default(); players],
+            rng: StdRng::seed_from_u64(config.seed),
+        }
+    }
+
+    pub fn restart(&mut self) {
+        *self = Simulation::new(self.config, self.map.clone());
+    }
+
+    /// Applies the players' inputs and advances the world by one tick.
+    pub fn step(&mut self, inputs: &TickInputs) -> TickEvents {
+        let mut events = TickEvents::default();
+        if self.game_over {
+            return events;
+        }
+
+        // Key presses only queue turns; the snakes themselves move together
+        // on the movement clock
+        for player in 0..self.snakes.len() {
+            if let Some(dir) = inputs.directions[player]
+                && self.alive[player]
+            {
+                self.queue_turn(player, dir);
+            }
+        }
+
+        self.advance(&mut events);
+        events
+    }
+
+    fn advance(&mut self, events: &mut TickEvents) {
+        if self.game_over {
+            return;
+        }
+        self.tick += 1;
+
+        // End timer mode once the clock runs out, highest score winning
+        if self.config.game_mode.is_timed_mode() && self.tick >= self.time_limit_ticks {
+            let result = self.score_result();
+            self.end_game(result, events);
+            return;
+        }
+
+        self.update_foods();
+        self.update_power_ups();
+
+        // Spawn enemies based on level - starting from level 1. Each level
+        // up to 5 brings in a new kind, and level 7 one more of any kind:
+        // Level 1: 1 enemy, Level 2: 2, ..., Level 5: 5, Level 7+: 6
+        let target_enemy_count = match self.level {
+            1..=4 => self.level as usize,
+            5..=6 => 5,
+            _ => 6,
+        };
+
+        while self.enemies.len() < target_enemy_count {
+            self.spawn_enemy();
+        }
+
+        // Update enemy movement, at half speed while anyone has slow-motion
+        let enemy_period = if self.slowed_by(None) { ENEMY_MOVE_TICKS * 2 } else { ENEMY_MOVE_TICKS };
+        self.enemy_move_ticks += 1;
+        if self.enemy_move_ticks >= enemy_period {
+            self.move_enemies();
+            self.enemy_move_ticks = 0;
+        }
+
+        // Calculate moving period based on level and game mode. Snakes on
+        // the same period move together; speed and slow fruit and other
+        // players' slow-motion pull a snake's clock off the others'.
+        let base_speed = self.config.game_mode.get_base_speed();
+        let speed_multiplier = self.config.game_mode.get_speed_multiplier();
+        let base_period = base_speed / (1.0 + (self.level as f64) * speed_multiplier);
+        let slowed: Vec<bool> = (0..self.snakes.len()).map(|player| self.slowed_by(Some(player))).collect();
+        let due: Vec<bool> = self.tempo.iter_mut().zip(&mut self.move_ticks).zip(slowed)
+            .map(|(((factor, ticks_left), move_ticks), slowed)| {
+                if *ticks_left > 0 {
+                    *ticks_left -= 1;
+                } else {
+                    *factor = 1.0;
+                }
+                *move_ticks += 1;
+                let slow_motion = if slowed { SLOW_MOTION_FACTOR } else { 1.0 };
+                *move_ticks >= to_ticks(base_period * *factor * slow_motion)
+            })
+            .collect();
+        if due.contains(&true) {
+            self.move_snakes(&due, events);
+        }
+
+        if !self.game_over && self.goal_progress().is_some_and(|(goal, progress)| progress >= goal.target()) {
+            self.goal_reached = true;
+            self.end_game(None, events);
+        }
+    }
+
+    // Queues `dir` unless the buffer is full or it would be a no-op or a
+    // reversal of the direction the snake will have by the time it applies
+    fn queue_turn(&mut self, player: usize, dir: Direction) {
+        let buffer_size = self.config.input_buffer.clamp(MIN_INPUT_BUFFER, MAX_INPUT_BUFFER);
+        let queue = &mut self.turns[player];
+        let effective = queue.back().copied().unwrap_or(self.snakes[player].head_direction());
+        if queue.len() < buffer_size && dir != effective && dir != effective.opposite() {
+            queue.push_back(dir);
+        }
+    }
+
+    // Counts down timed foods, keeps an apple on the board and now and then
+    // adds one more of a random kind
+    fn update_foods(&mut self) {
+        self.foods.retain_mut(|food| food.tick());
+        if !self.foods.iter().any(|food| food.kind == FoodKind::Apple) {
+            self.add_food(FoodKind::Apple);
+        }
+
+        self.food_ticks += 1;
+        if self.food_ticks >= EXTRA_FOOD_TICKS {
+            self.food_ticks = 0;
+            if self.foods.len() < MAX_FOODS {
+                let kind = FoodKind::from_roll(self.rng.random_range(0..FoodKind::total_weight()));
+                self.add_food(kind);
+            }
+        }
+    }
+
+    // Counts down running power-ups and items on the board, and puts out a
+    // new item now and then if the mode has any
+    fn update_power_ups(&mut self) {
+        for active in &mut self.power_ups {
+            active.tick();
+        }
+        self.power_up_items.retain_mut(|item| item.tick());
+
+        let kinds = self.config.game_mode.power_ups();
+        self.power_up_ticks += 1;
+        if self.power_up_ticks >= POWER_UP_TICKS && !kinds.is_empty() {
+            self.power_up_ticks = 0;
+            if self.power_up_items.is_empty() {
+                let kind = kinds[self.rng.random_range(0..kinds.len())];
+                if let Some((x, y)) = self.random_free_cell() {
+                    self.power_up_items.push(PowerUp::new(x, y, kind));
+                }
+            }
+        }
+    }
+
+    // Whether slow-motion from a living player other than `player` is
+    // running; `None` asks on behalf of the enemies
+    fn slowed_by(&self, player: Option<usize>) -> bool {
+        (0..self.snakes.len()).any(|other| {
+            Some(other) != player && self.alive[other] && self.power_ups[other].is_active(PowerUpKind::SlowMotion)
+        })
+    }
+
+    fn food_at(&self, position: (i32, i32)) -> Option<usize> {
+        self.foods.iter().position(|food| food.position() == position)
+    }
+
+    fn check_eating(&mut self, player: usize, events: &mut TickEvents) {
+        let Some(index) = self.food_at(self.snakes[player].head_position()) else {
+            return;
+        };
+
+        let kind = self.foods.remove(index).kind;
+        match kind.growth() {
+            growth if growth > 0 => self.snakes[player].grow(growth as usize),
+            growth => self.snakes[player].shrink(growth.unsigned_abs() as usize),
+        }
+        if let Some(factor) = kind.tempo() {
+            self.tempo[player] = (factor, TEMPO_SECONDS * TICK_RATE);
+        }
+
+        self.scores[player] += kind.points() * self.power_ups[player].score_multiplier();
+        self.foods_eaten += 1;
+        if kind == FoodKind::Apple {
+            self.apples_eaten += 1;
+        }
+        events.ate[player] = true;
+        self.update_level(events);
+    }
+
+    fn check_power_up(&mut self, player: usize) {
+        let head = self.snakes[player].head_position();
+        if let Some(index) = self.power_up_items.iter().position(|item| item.position() == head) {
+            let kind = self.power_up_items.remove(index).kind;
+            self.power_ups[player].add(kind);
+        }
+    }
+
+    // Steps from `from` to `to` along one axis of a board `size` cells
+    // across, border included, the short way round if the walls wrap
+    fn axis_offset(&self, from: i32, to: i32, size: i32) -> i32 {
+        let offset = to - from;
+        if self.config.wrap_walls && offset.abs() * 2 > size - 2 {
+            offset - offset.signum() * (size - 2)
+        } else {
+            offset
+        }
+    }
+
+    // Pulls every food within reach of a magnet holder's head one cell
+    // closer, onto free cells only
+    fn pull_food(&mut self, player: usize) {
+        let (head_x, head_y) = self.snakes[player].head_position();
+        for index in 0..self.foods.len() {
+            let (x, y) = self.foods[index].position();
+            let dx = self.axis_offset(x, head_x, self.config.width);
+            let dy = self.axis_offset(y, head_y, self.config.height);
+            if dx.abs() + dy.abs() > MAGNET_RANGE {
+                continue;
+            }
+            // Close the wider gap first
+            let mut step = if dx.abs() >= dy.abs() { (x + dx.signum(), y) } else { (x, y + dy.signum()) };
+            if self.config.wrap_walls {
+                step = wrap_position(step.0, step.1, self.config.width, self.config.height);
+            }
+            if self.is_free(step) {
+                self.foods[index].x = step.0;
+                self.foods[index].y = step.1;
+            }
+        }
+    }
+
+    fn update_level(&mut self, events: &mut TickEvents) {
+        let new_level = (self.foods_eaten / FOOD_PER_LEVEL) + 1;
+        if new_level > self.level {
+            self.level = new_level;
+            events.level_up = true;
+        }
+    }
+
+    // What `player` crashes into moving to `target`, if anything, given where
+    // every snake is heading this tick and which of them already crash
+    fn crash(&self, player: usize, target: (i32, i32), targets: &[Option<(i32, i32)>], crashed: &[bool]) -> Option<DeathCause> {
+        let (x, y) = target;
+
+        // Check wall collision (a wrapping snake never reaches the border)
+        if x <= 0 || y <= 0 || x >= self.config.width - 1 || y >= self.config.height - 1 {
+            return Some(DeathCause::Wall);
+        }
+        if self.map.is_wall(x, y) {
+            return Some(DeathCause::Wall);
+        }
+
+        // Invincibility shrugs off everything but walls, and ghosts pass
+        // through their own body
+        let active = &self.power_ups[player];
+        if active.is_active(PowerUpKind::Invincible) {
+            return None;
+        }
+        let ghost = active.is_active(PowerUpKind::Ghost);
+
+        // Check collision with corpses and enemies
+        if self.obstacles.contains(&target) {
+            return Some(DeathCause::Corpse);
+        }
+        if self.enemies.iter().any(|enemy| enemy.check_collision(x, y)) {
+            return Some(DeathCause::Enemy);
+        }
+
+        for (other, snake) in self.snakes.iter().enumerate() {
+            if !self.alive[other] || (ghost && other == player) {
+                continue;
+            }
+
+            if other != player {
+                // Two heads entering the same cell, or swapping places
+                if targets[other] == Some(target) {
+                    return Some(DeathCause::HeadOn);
+                }
+                if targets[other] == Some(self.snakes[player].head_position()) && target == snake.head_position() {
+                    return Some(DeathCause::HeadOn);
+                }
+            }
+
+            // A body's last block moves out of the way unless the snake
+            // grows this tick, crashes or isn't due to move and stays put
+            let grows = targets[other]
+                .and_then(|target| self.food_at(target))
+                .is_some_and(|index| self.foods[index].kind.growth() > 0);
+            let blocks = if grows || crashed[other] || targets[other].is_none() {
+                snake.contains(x, y)
+            } else {
+                snake.overlap_tail(x, y)
+            };
+            if blocks {
+                return Some(if other == player { DeathCause::OwnBody } else { DeathCause::OtherSnake });
+            }
+        }
+        None
+    }
+
+    // Moves every living snake that is `due` one step. All moves are worked
+    // out from the same board first, so no player gets first claim on a cell
+    // or the food.
+    fn move_snakes(&mut self, due: &[bool], events: &mut TickEvents) {
+        let players = self.snakes.len();
+        let targets: Vec<Option<(i32, i32)>> = (0..players)
+            .map(|player| {
+                (self.alive[player] && due[player])
+                    .then(|| self.snakes[player].next_head(self.turns[player].front().copied()))
+            })
+            .collect();
+        for player in (0..players).filter(|&player| due[player]) {
+            self.move_ticks[player] = 0;
+        }
+
+        // A crash can keep another snake's tail in place, so repeat until
+        // no new crashes turn up
+        let mut crashed = vec![false; players];
+
+        loop {
+            let mut changed = false;
+            for player in 0..players {
+                if let Some(target) = targets[player]
+                    && !crashed[player]
+                    && let Some(cause) = self.crash(player, target, &targets, &crashed)
+                {
+                    crashed[player] = true;
+                    self.death_causes[player] = Some(cause);
+                    changed = true;
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+
+        for (player, _) in crashed.iter().enumerate().filter(|(_, crashed)| **crashed) {
+            self.kill(player, events);
+        }
+        for player in 0..players {
+            if targets[player].is_some() && !crashed[player] {
+                self.snakes[player].move_forward(self.turns[player].pop_front());
+                events.snakes_moved = true;
+                self.check_eating(player, events);
+                self.check_power_up(player);
+                if self.power_ups[player].is_active(PowerUpKind::Magnet) {
+                    self.pull_food(player);
+                }
+            }
+        }
+
+        let deaths = crashed.iter().filter(|&&crashed| crashed).count();
+        if deaths > 0 {
+            self.check_survivors(deaths, events);
+        }
+    }
+
+    // Moves every enemy, then lets thieves take any food they reached. The
+    // apple comes back somewhere else next tick. Each enemy blocks the cell
+    // it moves into for the ones after it, so no two end up together.
+    fn move_enemies(&mut self) {
+        let mut surroundings = Surroundings {
+            grid: Grid::from_sim(self, false),
+            prey: (0..self.snakes.len())
+                .filter(|&player| self.alive[player])
+                .map(|player| self.snakes[player].next_head(self.turns[player].front().copied()))
+                .collect(),
+            foods: self.foods.iter().map(|food| food.position()).collect(),
+        };
+        for enemy in &mut self.enemies {
+            enemy.update(&surroundings, &mut self.rng);
+            let (x, y) = enemy.position();
+            surroundings.grid.block(x, y);
+        }
+
+        let enemies = &self.enemies;
+        self.foods.retain(|food| {
+            !enemies.iter().any(|enemy| enemy.kind() == EnemyKind::Thief && enemy.position() == food.position())
+        });
+    }
+
+    // The kind of the next enemy: the earliest one unlocked by this level
+    // that isn't on the board yet, or a random unlocked one
+    fn next_enemy_kind(&mut self) -> EnemyKind {
+        let unlocked: Vec<EnemyKind> =
+            ALL_ENEMY_KINDS.into_iter().filter(|kind| kind.first_level() <= self.level).collect();
+        match unlocked.iter().find(|&&kind| !self.enemies.iter().any(|enemy| enemy.kind() == kind)) {
+            Some(&kind) => kind,
+            None => unlocked[self.rng.random_range(0..unlocked.len())],
+        }
+    }
+
+    // A random cell for a new enemy: inside one of the map's enemy zones,
+    // or anywhere on the board when it has none
+    fn random_enemy_cell(&mut self) -> (i32, i32) {
+        if self.map.enemy_zones.is_empty() {
+            return (self.rng.random_range(1..self.config.width - 1), self.rng.random_range(1..self.config.height - 1));
+        }
+        let zone = self.map.enemy_zones[self.rng.random_range(0..self.map.enemy_zones.len())];
+        (self.rng.random_range(zone.x..zone.x + zone.width), self.rng.random_range(zone.y..zone.y + zone.height))
+    }
+
+    fn spawn_enemy(&mut self) {
+        // Spawn enemy away from the snakes and food
+        let (mut enemy_x, mut enemy_y) = self.random_enemy_cell();
+
+        // Make sure enemy doesn't spawn on snakes, food, or other enemies
+        let mut attempts = 0;
+        loop {
+            let mut valid_position = true;
+
+            // Check snake and corpse collisions
+            for (snake, alive) in self.snakes.iter().zip(&self.alive) {
+                if *alive && (snake.head_position() == (enemy_x, enemy_y) || snake.overlap_tail(enemy_x, enemy_y)) {
+                    valid_position = false;
+                }
+            }
+            if self.map.is_wall(enemy_x, enemy_y) || self.obstacles.contains(&(enemy_x, enemy_y)) {
+                valid_position = false;
+            }
+
+            // Check food collision
+            if self.food_at((enemy_x, enemy_y)).is_some() {
+                valid_position = false;
+            }
+
+            // Check other enemies
+            for existing_enemy in &self.enemies {
+                if existing_enemy.check_collision(enemy_x, enemy_y) {
+                    valid_position = false;
+                    break;
+                }
+            }
+
+            if valid_position {
+                break;
+            }
+
+            (enemy_x, enemy_y) = self.random_enemy_cell();
+            attempts += 1;
+            if attempts > 100 {
+                // Fallback: spawn in the centre of the board
+                enemy_x = self.config.width / 2;
+                enemy_y = self.config.height / 2;
+                break;
+            }
+        }
+
+        let kind = self.next_enemy_kind();
+        self.enemies.push(Enemy::new(enemy_x, enemy_y, kind));
+    }
+
+    // Places a food of `kind` on a free cell. Gives up on a crowded board
+    // and leaves it for a later tick.
+    fn add_food(&mut self, kind: FoodKind) {
+        if let Some((x, y)) = self.random_free_cell() {
+            self.foods.push(Food::new(x, y, kind));
+        }
+    }
+
+    // Whether food or a power-up may sit at `cell`: nothing else is there
+    // and the map allows food
+    fn is_free(&self, cell: (i32, i32)) -> bool {
+        let (x, y) = cell;
+        self.map.allows_food(x, y)
+            && !self.obstacles.contains(&cell)
+            && self.food_at(cell).is_none()
+            && !self.power_up_items.iter().any(|item| item.position() == cell)
+            && !self.enemies.iter().any(|enemy| enemy.check_collision(x, y))
+            && !(0..self.snakes.len()).any(|player| self.alive[player] && self.snakes[player].contains(x, y))
+    }
+
+    // A random free cell, or None if a hundred tries find nothing
+    fn random_free_cell(&mut self) -> Option<(i32, i32)> {
+        for _ in 0..100 {
+            let cell = (self.rng.random_range(1..self.config.width - 1), self.rng.random_range(1..self.config.height - 1));
+            if self.is_free(cell) {
+                return Some(cell);
+            }
+        }
+        None
+    }
+
+    fn kill(&mut self, player: usize, events: &mut TickEvents) {
+        self.alive[player] = false;
+        events.died[player] = true;
+        if self.config.corpse_rule == CorpseRule::Obstacle {
+            self.obstacles.extend(self.snakes[player].body());
+        }
+    }
+
+    // Ends the match once the deaths so far decide it
+    fn check_survivors(&mut self, deaths_this_tick: usize, events: &mut TickEvents) {
+        let survivors: Vec<usize> = (0..self.alive.len()).filter(|&player| self.alive[player]).collect();
+        if self.config.player_mode != PlayerMode::Versus {
+            // Solo and co-op play on until nobody is left
+            if survivors.is_empty() {
+                self.end_game(None, events);
+            }
+            return;
+        }
+
+        match survivors[..] {
+            // Snakes that die together draw; otherwise the last one
+            // standing had the chance to outscore the rest
+            [] if deaths_this_tick > 1 => self.end_game(Some(MatchResult::Draw), events),
+            [] => {
+                let result = self.score_result();
+                self.end_game(result, events);
+            }
+            [survivor] if self.config.survivor_rule == SurvivorRule::WinsNow => {
+                self.end_game(Some(MatchResult::Winner(survivor)), events);
+            }
+            _ => {}
+        }
+    }
+
+    /// Versus result by points: the single highest score wins. This is how
+    /// Timer matches end, and how a match cut short can be judged.
+    pub fn score_result(&self) -> Option<MatchResult> {
+        if self.config.player_mode != PlayerMode::Versus {
+            return None;
+        }
+        let best = self.scores.iter().copied().max().unwrap_or(0);
+        let mut leaders = (0..self.scores.len()).filter(|&player| self.scores[player] == best);
+        match (leaders.next(), leaders.next()) {
+            (Some(winner), None) => Some(MatchResult::Winner(winner)),
+            _ => Some(MatchResult::Draw),
+        }
+    }
+
+    fn end_game(&mut self, result: Option<MatchResult>, events: &mut TickEvents) {
+        self.game_over = true;
+        self.result = result;
+        self.final_level = self.level;
+        events.game_over = true;
+    }
+
+    pub fn config(&self) -> &MatchConfig {
+        &self.config
+    }
+
+    pub fn snakes(&self) -> &[Snake] {
+        &self.snakes
+    }
+
+    pub fn is_alive(&self, player: usize) -> bool {
+        self.alive[player]
+    }
+
+    /// What `player`'s snake crashed into, once it is dead.
+    pub fn death_cause(&self, player: usize) -> Option<DeathCause> {
+        self.death_causes[player]
+    }
+
+    /// Turns `player` has queued that their snake hasn't made yet.
+    pub fn queued_turns(&self, player: usize) -> usize {
+        self.turns[player].len()
+    }
+
+    pub fn map(&self) -> &Level {
+        &self.map
+    }
+
+    /// Cells left blocked by dead snakes.
+    pub fn obstacles(&self) -> &[(i32, i32)] {
+        &self.obstacles
+    }
+
+    pub fn foods(&self) -> &[Food] {
+        &self.foods
+    }
+
+    pub fn enemies(&self) -> &[Enemy] {
+        &self.enemies
+    }
+
+    pub fn width(&self) -> i32 {
+        self.config.width
+    }
+
+    pub fn height(&self) -> i32 {
+        self.config.height
+    }
+
+    pub fn game_mode(&self) -> GameMode {
+        self.config.game_mode
+    }
+
+    pub fn player_mode(&self) -> PlayerMode {
+        self.config.player_mode
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.config.seed
+    }
+
+    pub fn scores(&self) -> &[i32] {
+        &self.scores
+    }
+
+    /// Points pooled by every player, the score that counts in co-op.
+    pub fn team_score(&self) -> i32 {
+        self.scores.iter().sum()
+    }
+
+    pub fn get_level(&self) -> i32 {
+        self.level
+    }
+
+    /// Ticks since the match started.
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    pub fn final_level(&self) -> i32 {
+        self.final_level
+    }
+
+    pub fn is_game_over(&self) -> bool {
+        self.game_over
+    }
+
+    /// The match's goal and how far towards it the players are: apples
+    /// eaten, whole seconds survived or the longest living snake.
+    pub fn goal_progress(&self) -> Option<(Goal, u64)> {
+        let goal = self.config.goal?;
+        let progress = match goal {
+            Goal::Eat(_) => self.apples_eaten,
+            Goal::Survive(_) => self.tick / TICK_RATE as u64,
+            Goal::Length(_) => (0..self.snakes.len())
+                .filter(|&player| self.alive[player])
+                .map(|player| self.snakes[player].len() as u64)
+                .max()
+                .unwrap_or(0),
+        };
+        Some((goal, progress))
+    }
+
+    pub fn goal_reached(&self) -> bool {
+        self.goal_reached
+    }
+
+    /// The versus outcome once the match is over.
+    pub fn result(&self) -> Option<MatchResult> {
+        self.result
+    }
+
+    /// Seconds left in Timer mode.
+    pub fn time_remaining(&self) -> f64 {
+        self.time_limit_ticks.saturating_sub(self.tick) as f64 / TICK_RATE as f64
+    }
+
+    /// Power-ups lying on the board.
+    pub fn power_up_items(&self) -> &[PowerUp] {
+        &self.power_up_items
+    }
+
+    /// Power-ups `player` has running.
+    pub fn power_ups(&self, player: usize) -> &ActivePowerUps {
+        &self.power_ups[player]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(player_mode: PlayerMode, input_buffer: usize) -> MatchConfig {
+        MatchConfig {
+            width: 30,
+            height: 30,
+            game_mode: GameMode::Medium,
+            player_mode,
+            corpse_rule: CorpseRule::Remove,
+            survivor_rule: SurvivorRule::WinsNow,
+            input_buffer,
+            wrap_walls: false,
+            goal: None,
+            seed: 1,
+        }
+    }
+
+    // A versus match on an open board with `snakes` in place of the usual
+    // starting ones and nothing else on it
+    fn versus(snakes: Vec<Snake>) -> Simulation {
+        let mut sim = Simulation::new(config(PlayerMode::Versus, 2), Level::open(30, 30));
+        sim.snakes = snakes;
+        sim
+    }
+
+    // A solo match with its snake at (10, 10) heading right
+    fn solo(input_buffer: usize) -> Simulation {
+        let mut sim = Simulation::new(config(PlayerMode::Solo, input_buffer), Level::open(30, 30));
+        sim.snakes = vec![Snake::facing(10, 10, Direction::Right)];
+        sim
+    }
+
+    // Moves every snake one step, whatever their clocks say
+    fn move_all(sim: &mut Simulation) -> TickEvents {
+        let mut events = TickEvents::default();
+        sim.move_snakes(&vec![true; sim.snakes.len()], &mut events);
+        events
+    }
+
+    #[test]
+    fn head_on_kills_both() {
+        let mut sim = versus(vec![
+            Snake::facing(10, 10, Direction::Right),
+            Snake::facing(12, 10, Direction::Left),
+        ]);
+        let events = move_all(&mut sim);
+        assert!(events.died[0] && events.died[1]);
+        assert!(sim.death_cause(0) == Some(DeathCause::HeadOn));
+        assert!(sim.death_cause(1) == Some(DeathCause::HeadOn));
+        assert!(sim.result() == Some(MatchResult::Draw));
+    }
+
+    #[test]
+    fn swapping_heads_kills_both() {
+        let mut sim = versus(vec![
+            Snake::facing(10, 10, Direction::Right),
+            Snake::facing(11, 10, Direction::Left),
+        ]);
+        move_all(&mut sim);
+        assert!(!sim.is_alive(0) && !sim.is_alive(1));
+        assert!(sim.death_cause(0) == Some(DeathCause::HeadOn));
+        assert!(sim.death_cause(1) == Some(DeathCause::HeadOn));
+    }
+
+    #[test]
+    fn following_a_tail_is_allowed() {
+        // Player 1 moves into (11, 9) just as player 2's tail leaves it
+        let mut sim = versus(vec![
+            Snake::facing(10, 9, Direction::Right),
+            Snake::facing(11, 11, Direction::Down),
+        ]);
+        move_all(&mut sim);
+        assert!(sim.is_alive(0) && sim.is_alive(1));
+        assert_eq!(sim.snakes[0].head_position(), (11, 9));
+
+        // Unless that snake grows this move and its tail stays put
+        let mut sim = versus(vec![
+            Snake::facing(10, 9, Direction::Right),
+            Snake::facing(11, 11, Direction::Down),
+        ]);
+        sim.foods.push(Food::new(11, 12, FoodKind::Apple));
+        move_all(&mut sim);
+        assert!(sim.death_cause(0) == Some(DeathCause::OtherSnake));
+        assert!(sim.is_alive(1));
+    }
+
+    #[test]
+    fn heads_meeting_on_food_both_die_without_eating() {
+        let mut sim = versus(vec![
+            Snake::facing(10, 10, Direction::Right),
+            Snake::facing(12, 10, Direction::Left),
+        ]);
+        sim.foods.push(Food::new(11, 10, FoodKind::Apple));
+        let events = move_all(&mut sim);
+        assert!(!sim.is_alive(0) && !sim.is_alive(1));
+        assert!(!events.ate[0] && !events.ate[1]);
+        assert_eq!(sim.scores(), &[0, 0]);
+        assert_eq!(sim.foods().len(), 1);
+    }
+
+    #[test]
+    fn reversal_is_checked_against_last_queued_turn() {
+        let mut sim = solo(3);
+        sim.queue_turn(0, Direction::Up);
+        sim.queue_turn(0, Direction::Down); // Reverses the queued Up
+        assert_eq!(sim.turns[0].len(), 1);
+        sim.queue_turn(0, Direction::Left); // Reverses the heading, but not Up
+        assert_eq!(sim.turns[0].len(), 2);
+    }
+
+    #[test]
+    fn buffer_depth_is_clamped() {
+        let turns = [Direction::Up, Direction::Left, Direction::Down, Direction::Right, Direction::Up];
+        for (input_buffer, expected) in [(0, MIN_INPUT_BUFFER), (10, MAX_INPUT_BUFFER)] {
+            let mut sim = solo(input_buffer);
+            for turn in turns {
+                sim.queue_turn(0, turn);
+            }
+            assert_eq!(sim.turns[0].len(), expected);
+        }
+    }
+
+    #[test]
+    fn queued_turns_apply_one_per_move() {
+        let mut sim = solo(2);
+        sim.queue_turn(0, Direction::Up);
+        sim.queue_turn(0, Direction::Left);
+
+        move_all(&mut sim);
+        assert!(sim.snakes[0].head_direction() == Direction::Up);
+        assert_eq!(sim.snakes[0].head_position(), (10, 9));
+
+        move_all(&mut sim);
+        assert!(sim.snakes[0].head_direction() == Direction::Left);
+        assert_eq!(sim.snakes[0].head_position(), (9, 9));
+        assert!(sim.turns[0].is_empty());
+    }
+
+    #[test]
+    fn enemies_never_share_a_cell() {
+        // Two thieves either side of the same food
+        let mut sim = versus(vec![
+            Snake::facing(5, 20, Direction::Right),
+            Snake::facing(25, 20, Direction::Left),
+        ]);
+        sim.enemies = vec![Enemy::new(10, 10, EnemyKind::Thief), Enemy::new(12, 10, EnemyKind::Thief)];
+        sim.foods.push(Food::new(11, 10, FoodKind::Apple));
+        sim.move_enemies();
+        sim.move_enemies(); // Enemies move every other update
+        assert_eq!(sim.enemies[0].position(), (11, 10));
+        assert_ne!(sim.enemies[1].position(), (11, 10));
+    }
+
+    #[test]
+    fn eating_changes_length_by_growth() {
+        let mut sim = solo(2);
+        sim.foods.push(Food::new(11, 10, FoodKind::Apple));
+        move_all(&mut sim);
+        assert_eq!(sim.snakes[0].len() as i32, 3 + FoodKind::Apple.growth());
+
+        // Berries shrink, but never below three blocks
+        sim.foods.push(Food::new(12, 10, FoodKind::Berry));
+        move_all(&mut sim);
+        assert_eq!(sim.snakes[0].len(), 3);
+    }
+
+    #[test]
+    fn only_apples_count_towards_eat_goals() {
+        let mut sim = solo(2);
+        sim.config.goal = Some(Goal::Eat(5));
+        sim.foods.push(Food::new(11, 10, FoodKind::Golden));
+        sim.foods.push(Food::new(12, 10, FoodKind::Apple));
+        move_all(&mut sim);
+        move_all(&mut sim);
+        assert!(sim.goal_progress().is_some_and(|(_, progress)| progress == 1));
+    }
+
+    #[test]
+    fn magnet_pulls_across_wrapping_walls() {
+        let mut config = config(PlayerMode::Solo, 2);
+        config.wrap_walls = true;
+        let mut sim = Simulation::new(config, Level::open(30, 30));
+        let mut snake = Snake::facing(1, 10, Direction::Left);
+        snake.set_wrap(30, 30);
+        sim.snakes = vec![snake];
+        // Two cells from the head the short way round, 26 the long way
+        sim.foods.push(Food::new(27, 10, FoodKind::Apple));
+        sim.pull_food(0);
+        assert_eq!(sim.foods[0].position(), (28, 10));
+    }
+}
@@ -1,13 +1,4 @@
 use std::collections::LinkedList;
-use piston_window::{Context, G2d, ellipse};
-use piston_window::types::Color;
-
-use crate::draw::{draw_circle, to_coord};
-
-const SNAKE1_BODY_COLOR: Color = [0.2, 0.7, 0.2, 1.0];  // Green for player 1
-const SNAKE1_HEAD_COLOR: Color = [0.3, 0.9, 0.3, 1.0];
-const SNAKE2_BODY_COLOR: Color = [0.2, 0.2, 0.7, 1.0];  // Blue for player 2
-const SNAKE2_HEAD_COLOR: Color = [0.3, 0.3, 0.9, 1.0];
 
 #[derive(Copy, Clone, PartialEq)]
 pub enum Direction {
@@ -18,6 +9,16 @@ pub enum Direction {
 }
 
 impl Direction {
+    /// One step in this direction as (dx, dy).
+    pub fn offset(&self) -> (i32, i32) {
+        match *self {
+            Direction::Up => (0, -1),
+            Direction::Down => (0, 1),
+            Direction::Left => (-1, 0),
+            Direction::Right => (1, 0),
+        }
+    }
+
     pub fn opposite(&self) -> Direction {
         match *self {
             Direction::Up => Direction::Down,
@@ -28,6 +29,12 @@ impl Direction {
     }
 }
 
+/// Wraps (x, y) back inside the playing area of a `width` x `height` board,
+/// whose outermost ring of cells is the border.
+pub fn wrap_position(x: i32, y: i32, width: i32, height: i32) -> (i32, i32) {
+    (1 + (x - 1).rem_euclid(width - 2), 1 + (y - 1).rem_euclid(height - 2))
+}
+
 #[derive(Debug, Clone)]
 struct Block {
     x: i32,
@@ -38,66 +45,44 @@ pub struct Snake {
     direction: Direction,
     body: LinkedList<Block>,
     tail: Option<Block>,
+    wrap: Option<(i32, i32)>, // Board size to wrap around, if the walls wrap
 }
 
 impl Snake {
-    pub fn new(x: i32, y: i32) -> Snake {
+    /// A three-block snake with its head at (x, y), heading `direction`.
+    pub fn facing(x: i32, y: i32, direction: Direction) -> Snake {
+        let (dx, dy) = direction.offset();
         let mut body: LinkedList<Block> = LinkedList::new();
-        body.push_back(Block { x: x + 2, y });
-        body.push_back(Block { x: x + 1, y });
-        body.push_back(Block { x, y });
+        for i in 0..3 {
+            body.push_back(Block { x: x - dx * i, y: y - dy * i });
+        }
 
         Snake {
-            direction: Direction::Right,
+            direction,
             body,
             tail: None,
+            wrap: None,
         }
     }
-    
-    pub fn new_left(x: i32, y: i32) -> Snake {
-        let mut body: LinkedList<Block> = LinkedList::new();
-        body.push_back(Block { x, y });
-        body.push_back(Block { x: x + 1, y });
-        body.push_back(Block { x: x + 2, y });
 
-        Snake {
-            direction: Direction::Left,  // Facing left
-            body,
-            tail: None,
-        }
+    /// Makes the snake leave one edge of a `width` x `height` board and
+    /// come back in at the opposite one.
+    pub fn set_wrap(&mut self, width: i32, height: i32) {
+        self.wrap = Some((width, height));
     }
 
-    pub fn draw(&self, con: &Context, g: &mut G2d, is_player1: bool) {
-        let body_color = if is_player1 { SNAKE1_BODY_COLOR } else { SNAKE2_BODY_COLOR };
-        let head_color = if is_player1 { SNAKE1_HEAD_COLOR } else { SNAKE2_HEAD_COLOR };
-        
-        let mut is_head = true;
-        for block in &self.body {
-            if is_head {
-                // Draw head larger and brighter
-                draw_circle(head_color, block.x, block.y, con, g);
-                // Draw eyes on head
-                let eye_color: Color = [0.0, 0.0, 0.0, 1.0];
-                let gui_x = to_coord(block.x);
-                let gui_y = to_coord(block.y);
-                ellipse(
-                    eye_color,
-                    [gui_x + 6.0, gui_y + 6.0, 4.0, 4.0],
-                    con.transform,
-                    g,
-                );
-                ellipse(
-                    eye_color,
-                    [gui_x + 15.0, gui_y + 6.0, 4.0, 4.0],
-                    con.transform,
-                    g,
-                );
-                is_head = false;
-            } else {
-                // Draw body segments as circles
-                draw_circle(body_color, block.x, block.y, con, g);
-            }
-        }
+    /// Positions of every segment, head first.
+    pub fn body(&self) -> impl Iterator<Item = (i32, i32)> + '_ {
+        self.body.iter().map(|block| (block.x, block.y))
+    }
+
+    pub fn len(&self) -> usize {
+        self.body.len()
+    }
+
+    /// Always false: a snake never shrinks below three blocks.
+    pub fn is_empty(&self) -> bool {
+        self.body.is_empty()
     }
 
     pub fn head_position(&self) -> (i32, i32) {
@@ -106,31 +91,12 @@ impl Snake {
     }
 
     pub fn move_forward(&mut self, dir: Option<Direction>) {
-        match dir {
-            Some(d) => self.direction = d,
-            None => (),
+        if let Some(d) = dir {
+            self.direction = d;
         }
 
-        let (last_x, last_y): (i32, i32) = self.head_position();
-
-        let new_block = match self.direction {
-            Direction::Up => Block {
-                x: last_x,
-                y: last_y - 1,
-            },
-            Direction::Down => Block {
-                x: last_x,
-                y: last_y + 1,
-            },
-            Direction::Left => Block {
-                x: last_x - 1,
-                y: last_y,
-            },
-            Direction::Right => Block {
-                x: last_x + 1,
-                y: last_y,
-            },
-        };
+        let (x, y) = self.next_head(None);
+        let new_block = Block { x, y };
 
         self.body.push_front(new_block);
         let removed_block = self.body.pop_back().unwrap();
@@ -144,17 +110,12 @@ impl Snake {
     pub fn next_head(&self, dir: Option<Direction>) -> (i32, i32) {
         let (head_x, head_y): (i32, i32) = self.head_position();
 
-        let mut moving_dir = self.direction;
-        match dir {
-            Some(d) => moving_dir = d,
-            None => {},
-        }
+        let moving_dir = dir.unwrap_or(self.direction);
 
-        match moving_dir {
-            Direction::Up => (head_x, head_y - 1),
-            Direction::Down => (head_x, head_y + 1),
-            Direction::Left => (head_x - 1, head_y),
-            Direction::Right => (head_x + 1, head_y),
+        let (dx, dy) = moving_dir.offset();
+        match self.wrap {
+            Some((width, height)) => wrap_position(head_x + dx, head_y + dy, width, height),
+            None => (head_x + dx, head_y + dy),
         }
     }
 
@@ -163,6 +124,27 @@ impl Snake {
         self.body.push_back(blk);
     }
 
+    /// Adds `blocks` blocks where the last one just left. They stack up
+    /// there and unfold one per move.
+    pub fn grow(&mut self, blocks: usize) {
+        for _ in 0..blocks {
+            self.restore_tail();
+        }
+    }
+
+    /// Drops up to `blocks` blocks off the end, never going below the
+    /// three a snake starts with.
+    pub fn shrink(&mut self, blocks: usize) {
+        for _ in 0..blocks.min(self.body.len().saturating_sub(3)) {
+            self.body.pop_back();
+        }
+    }
+
+    /// Whether any block, the last one included, is at (x, y).
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        self.body().any(|position| position == (x, y))
+    }
+
     pub fn overlap_tail(&self, x: i32, y: i32) -> bool {
         let mut ch = 0;
         for block in &self.body {
@@ -175,6 +157,6 @@ impl Snake {
                 break;
             }
         }
-        return false;
+        false
     }
-}
\ No newline at end of file
+}